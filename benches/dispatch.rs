@@ -0,0 +1,47 @@
+// Dispatch overhead of the interpreter against the threaded backend.
+//
+//   cargo bench --bench dispatch
+
+use std::time::{Duration, Instant};
+
+use yjriver::runtime::{YjrEnviroment, Backend};
+
+const RUNS: u32 = 500;
+const ROUNDS: u32 = 10;
+
+fn time(script: &str, backend: Backend) -> Duration {
+    let mut env = YjrEnviroment::new(44100);
+    let mut rt = env.build(script);
+    rt.set_backend(backend);
+    for _ in 0..RUNS / 10 {
+        rt.run();
+        rt.stack.clear();
+    }
+
+    // best of some rounds, others are disturbed by the machine
+    (0..ROUNDS).map(|_| {
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            rt.run();
+            rt.stack.clear();
+        }
+        start.elapsed() / RUNS
+    }).min().unwrap()
+}
+
+fn main() {
+    let patches = [
+        ("numbers", "0 #loop 2000 1 + 0.5 * #end"),
+        ("user words", "#def step 1 + 0.5 * #end 0 #loop 2000 step #end"),
+        ("hash", "0 $x ! #loop 1000 $x @ 1 + $x ! #end"),
+        ("small blocks", "#loop 500 4 ones~ 0.5 swap * drop #end"),
+        ("signals", "64 #loop 50 dup 440 dsp.os.osc 0.5 swap * drop #end"),
+    ];
+
+    println!("{:<14} {:>14} {:>14} {:>8}", "patch", "interpreter", "threaded", "speedup");
+    for (name, script) in patches {
+        let a = time(script, Backend::Interpreter);
+        let b = time(script, Backend::Threaded);
+        println!("{:<14} {:>14?} {:>14?} {:>7.2}x", name, a, b, a.as_secs_f64() / b.as_secs_f64());
+    }
+}
@@ -0,0 +1,42 @@
+// Registers the generated Faust modules in src/faust/auto, each file is a
+// module and its dsp is the word `dsp.<name>`, name from the metadata.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn dsp_name(src: &str) -> Option<&str> {
+    let key = "m.declare(\"name\", \"";
+    let start = src.find(key)? + key.len();
+    let end = src[start..].find('"')?;
+    Some(&src[start..start + end])
+}
+
+fn main() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src").join("faust").join("auto");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir).unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().map(|e| e == "rs").unwrap_or(false))
+        .collect();
+    files.sort();
+
+    let mut modules = String::new();
+    let mut words = String::new();
+    for f in files.iter() {
+        let module = f.file_stem().unwrap().to_str().unwrap();
+        let src = fs::read_to_string(f).unwrap();
+        let name = match dsp_name(&src) {
+            Some(n) => n,
+            None => panic!("Faust module {:?} declares no name", f),
+        };
+        modules.push_str( &format!("#[path = {:?}]\npub mod {};\n", f.display().to_string(), module) );
+        words.push_str( &format!("    insert_faust_word::<auto::{}::dsp>(env, \"dsp.{}\");\n", module, name) );
+    }
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out.join("faust_auto.rs"), modules).unwrap();
+    fs::write(out.join("faust_words.rs"),
+              format!("fn insert_generated_words(env: &mut YjrEnviroment) {{\n{}}}\n", words)).unwrap();
+}
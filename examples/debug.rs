@@ -0,0 +1,20 @@
+// Steps a script file with the debugger, commands are read from stdin.
+//
+//     cargo run --example debug -- script.yjr
+
+use std::io;
+
+use yjriver::runtime::YjrEnviroment;
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(p) => p,
+        None => panic!("usage: debug <script>"),
+    };
+    let txt = std::fs::read_to_string(&path).unwrap();
+
+    let mut env = YjrEnviroment::new(44100);
+    let mut rt = env.build(&txt);
+    let mut dbg = rt.debugger();
+    dbg.repl(io::stdin().lock(), io::stdout()).unwrap();
+}
@@ -1,5 +1,5 @@
 use crate::TNT;
-use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, SharedVector};
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, SharedVector};
 use crate::vector::Vector;
 
 macro_rules! base_stack_op {
@@ -60,6 +60,89 @@ macro_rules! vector_creator {
 vector_creator!{Zeros, zeros}
 vector_creator!{Ones, ones}
 
+// v1 v2 ... n channels~ -> m
+struct Channels {}
+impl Channels {
+    pub fn new(_env: &YjrEnviroment)->Box<dyn NativeWord> {
+        Box::new(Channels {})
+    }
+}
+impl NativeWord for Channels {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let m = stack.pop_vector_list();
+        stack.push_multi(m);
+    }
+}
+
+// merging channels of two vector or multichannel items, lower item first
+struct Merge {}
+impl Merge {
+    pub fn new(_env: &YjrEnviroment)->Box<dyn NativeWord> {
+        Box::new(Merge {})
+    }
+}
+impl NativeWord for Merge {
+    fn run(&mut self, stack: &mut YjrStack) {
+        fn channels(item: YjrItem) -> Vec<SharedVector> {
+            match item {
+                YjrItem::V(v) => vec![v],
+                YjrItem::M(m) => m,
+                _ => panic!("Only vector or multichannel can be merged"),
+            }
+        }
+        let b = channels( stack.pop() );
+        let mut a = channels( stack.pop() );
+        a.extend(b);
+        stack.push_multi(a);
+    }
+}
+
+// m split~ -> v1 v2 ... n
+struct Split {}
+impl Split {
+    pub fn new(_env: &YjrEnviroment)->Box<dyn NativeWord> {
+        Box::new(Split {})
+    }
+}
+impl NativeWord for Split {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let m = stack.pop_multi();
+        stack.push_vector_list(m);
+    }
+}
+
+// m i chan~ -> v
+struct Chan {}
+impl Chan {
+    pub fn new(_env: &YjrEnviroment)->Box<dyn NativeWord> {
+        Box::new(Chan {})
+    }
+}
+impl NativeWord for Chan {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let i = stack.pop_number();
+        let m = stack.pop_multi();
+        if i.fract() != 0.0 || i < 0.0 || i as usize >= m.len() {
+            panic!("Channel index {} is out of {} channels", i, m.len());
+        }
+        stack.push_vector( m[i as usize].clone() );
+    }
+}
+
+// m nchan -> n
+struct NChan {}
+impl NChan {
+    pub fn new(_env: &YjrEnviroment)->Box<dyn NativeWord> {
+        Box::new(NChan {})
+    }
+}
+impl NativeWord for NChan {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let m = stack.pop_multi();
+        stack.push_number(m.len() as TNT);
+    }
+}
+
 pub fn insert_native_words(env: &mut YjrEnviroment) {
     // Stack Operator
     env.insert_native_word("drop",  DropW::new);
@@ -71,5 +154,50 @@ pub fn insert_native_words(env: &mut YjrEnviroment) {
     // creator of vector
     env.insert_native_word("zeros~", Zeros::new);
     env.insert_native_word("ones~",  Ones::new);
+
+    // multichannel
+    env.insert_native_word("channels~", Channels::new);
+    env.insert_native_word("merge~", Merge::new);
+    env.insert_native_word("split~", Split::new);
+    env.insert_native_word("chan~", Chan::new);
+    env.insert_native_word("nchan", NChan::new);
+
+    // stack effects
+    env.insert_native_effect("drop", "A --");
+    env.insert_native_effect("dup", "A -- A A");
+    env.insert_native_effect("dup2", "A B -- A B A B");
+    env.insert_native_effect("swap", "A B -- B A");
+    env.insert_native_effect("rot", "A B C -- B C A");
+    env.insert_native_effect("zeros~", "n -- v");
+    env.insert_native_effect("ones~", "n -- v");
+    env.insert_native_effect("channels~", "[v] -- m");
+    env.insert_native_effect("merge~", "v v -- m | v m -- m | m v -- m | m m -- m");
+    env.insert_native_effect("chan~", "m n -- v");
+    env.insert_native_effect("nchan", "m -- n");
 }
 
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::YjrEnviroment;
+
+    #[test]
+    fn multichannel() {
+        let mut env = YjrEnviroment::new(44100);
+        let txt = "8 ones~ 8 zeros~ 2 channels~
+                   0.5 swap * tanh
+                   dup 8 ones~ merge~ nchan
+                   swap dup 1 chan~ sum
+                   swap split~";
+        let mut rt = env.build(txt);
+        rt.run();
+
+        assert_eq!(rt.stack.pop_number(), 2.0);
+        let zero = rt.stack.pop_vector();
+        let half = rt.stack.pop_vector();
+        assert_eq!(half.vec().data(), &vec![0.5_f32.tanh(); 8]);
+        assert_eq!(zero.vec().data(), &vec![0.0; 8]);
+        assert_eq!(rt.stack.pop_number(), 0.0);
+        assert_eq!(rt.stack.pop_number(), 3.0);
+    }
+}
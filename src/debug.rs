@@ -0,0 +1,423 @@
+//! Step debugger and trace hooks.
+//!
+//! `YjrDebugger` executes a runtime one word at a time, it keeps its own
+//! frames of user words instead of the recursion of `YjrRuntime::run`,
+//! so a block can be stopped and inspected at any word.
+
+use std::io::{self, BufRead, Write};
+
+use crate::TNT;
+use crate::runtime::{YjrRuntime, YjrStack, YjrHash, YjrItem, SrcInfo};
+
+/// Event given to the trace hook around every native word.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Trace<'a> {
+    Before(&'a SrcInfo),
+    After(&'a SrcInfo),
+}
+
+pub type TraceHook = Box<dyn FnMut(Trace, &YjrStack)>;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Breakpoint {
+    Word(String),       // any word with this text
+    Line(usize),        // any word at this source line
+}
+
+impl Breakpoint {
+    fn hit(&self, info: &SrcInfo) -> bool {
+        match self {
+            Breakpoint::Word(w) => *w == info.word,
+            Breakpoint::Line(l) => *l == info.line,
+        }
+    }
+}
+
+/// Why the debugger stopped.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Stop {
+    Step,               // one word is executed
+    Breakpoint(usize),  // the next word hits this breakpoint
+    Finished,           // the block is done, next step begins a new one
+}
+
+pub struct YjrDebugger<'a> {
+    rt: &'a mut YjrRuntime,
+    frames: Vec<(usize, usize)>,        // (binary, next word)
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl<'a> YjrDebugger<'a> {
+    pub fn new(rt: &'a mut YjrRuntime) -> Self {
+        YjrDebugger {
+            rt,
+            frames: Vec::new(),
+            breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, bp: Breakpoint) -> usize {
+        self.breakpoints.push(bp);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn remove_breakpoint(&mut self, i: usize) {
+        self.breakpoints.remove(i);
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn stack(&self) -> &YjrStack {
+        &self.rt.stack
+    }
+
+    pub fn hash(&self) -> &YjrHash {
+        &self.rt.hash
+    }
+
+    /// The word to be executed by next step, none between blocks.
+    pub fn current(&self) -> Option<&SrcInfo> {
+        self.frames.last().map(|&(i, j)| &self.rt.infos[i][j])
+    }
+
+    /// User words entered so far, from main to innermost, with the word
+    /// calling into the next frame.
+    pub fn backtrace(&self) -> Vec<(&str, &SrcInfo)> {
+        self.frames.iter().map(|&(i, j)| {
+            let j = if (i, j) == *self.frames.last().unwrap() { j } else { j - 1 };
+            (self.rt.names[i].as_str(), &self.rt.infos[i][j])
+        }).collect()
+    }
+
+    fn begin(&mut self) -> bool {
+        self.frames.push( (0, 0) );
+        self.rt.hash.moveto(0);
+        self.settle();
+        !self.frames.is_empty()
+    }
+
+    // leaving all finished frames
+    fn settle(&mut self) {
+        while let Some(&(i, j)) = self.frames.last() {
+            if j < self.rt.infos[i].len() {
+                break;
+            }
+            self.frames.pop();
+            if let Some(&(k, _)) = self.frames.last() {
+                self.rt.hash.moveto(k);
+            }
+        }
+    }
+
+    fn hit(&self) -> Option<usize> {
+        let info = self.current()?;
+        self.breakpoints.iter().position(|bp| bp.hit(info))
+    }
+
+    /// Executes one word, entering a user word counts as one step.
+    pub fn step(&mut self) -> Stop {
+        if self.frames.is_empty() && !self.begin() {
+            return Stop::Finished;
+        }
+
+        let (i, j) = *self.frames.last().unwrap();
+        self.frames.last_mut().unwrap().1 = j + 1;
+        if let Some(w) = self.rt.exec(i, j) {
+            self.frames.push( (w, 0) );
+            self.rt.hash.moveto(w);
+        }
+        self.settle();
+
+        if self.frames.is_empty() {
+            Stop::Finished
+        } else {
+            Stop::Step
+        }
+    }
+
+    /// Runs until a breakpoint or the end of block.
+    pub fn cont(&mut self) -> Stop {
+        if self.frames.is_empty() {
+            if !self.begin() {
+                return Stop::Finished;
+            }
+            if let Some(k) = self.hit() {
+                return Stop::Breakpoint(k);
+            }
+        }
+        loop {
+            if self.step() == Stop::Finished {
+                return Stop::Finished;
+            }
+            if let Some(k) = self.hit() {
+                return Stop::Breakpoint(k);
+            }
+        }
+    }
+
+    /// Executes a command line of REPL or CLI, returns the text to show.
+    ///
+    /// ```text
+    /// step [n]            s [n]       execute n words
+    /// continue            c           run to next breakpoint or block end
+    /// stack                           show the stack, top last
+    /// hash                            show the hash of current word
+    /// where                           show the frames of user words
+    /// break [word|line]   b           add a breakpoint or list them
+    /// delete n                        remove a breakpoint
+    /// quit                q           leave `repl`
+    /// ```
+    pub fn command(&mut self, cmd: &str) -> String {
+        let args: Vec<&str> = cmd.split_whitespace().collect();
+        if args.is_empty() {
+            return String::new();
+        }
+        match args[0] {
+            "step" | "s" => {
+                let n = match args.get(1) {
+                    Some(n) => match n.parse::<usize>() {
+                        Ok(n) => n,
+                        Err(_) => return format!("bad step count '{}'", n),
+                    },
+                    None => 1,
+                };
+                let mut stop = Stop::Step;
+                for _ in 0..n {
+                    stop = self.step();
+                    if stop == Stop::Finished {
+                        break;
+                    }
+                }
+                self.describe(stop)
+            },
+            "continue" | "c" => {
+                let stop = self.cont();
+                self.describe(stop)
+            },
+            "stack" => {
+                let mut ret = format!("<{}>", self.rt.stack.size());
+                for item in self.rt.stack.items() {
+                    ret.push('\n');
+                    ret.push_str( &show_item(item) );
+                }
+                ret
+            },
+            "hash" => {
+                let mut ret = format!("hash of `{}`", self.rt.names[ self.rt.hash.target() ]);
+                for (k, item) in self.rt.hash.items() {
+                    ret.push_str( &format!("\n{} = {}", k, show_item(item)) );
+                }
+                ret
+            },
+            "where" => {
+                let mut ret = String::new();
+                for (name, info) in self.backtrace() {
+                    ret.push_str( &format!("`{}` at line {}: {}\n", name, info.line, info.word) );
+                }
+                if ret.is_empty() {
+                    ret.push_str("not running");
+                }
+                ret.trim_end().to_string()
+            },
+            "break" | "b" => {
+                if let Some(w) = args.get(1) {
+                    let bp = match w.parse::<usize>() {
+                        Ok(l) => Breakpoint::Line(l),
+                        Err(_) => Breakpoint::Word(w.to_string()),
+                    };
+                    let k = self.add_breakpoint(bp);
+                    return format!("breakpoint {}: {}", k, show_breakpoint(&self.breakpoints[k]));
+                }
+                let mut ret = String::new();
+                for (k, bp) in self.breakpoints.iter().enumerate() {
+                    ret.push_str( &format!("breakpoint {}: {}\n", k, show_breakpoint(bp)) );
+                }
+                ret.trim_end().to_string()
+            },
+            "delete" => {
+                match args.get(1).and_then(|k| k.parse::<usize>().ok()) {
+                    Some(k) if k < self.breakpoints.len() => {
+                        self.remove_breakpoint(k);
+                        format!("breakpoint {} deleted", k)
+                    },
+                    _ => "no such breakpoint".to_string(),
+                }
+            },
+            _ => format!("unknown command '{}'", args[0]),
+        }
+    }
+
+    /// Reads commands line by line until `quit` or end of input, writing
+    /// their results, e.g. `dbg.repl(io::stdin().lock(), io::stdout())`.
+    pub fn repl<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        write!(output, "(yjr) ")?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            if matches!(line.trim(), "quit" | "q") {
+                break;
+            }
+            let ret = self.command(&line);
+            if !ret.is_empty() {
+                writeln!(output, "{}", ret)?;
+            }
+            write!(output, "(yjr) ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    fn describe(&self, stop: Stop) -> String {
+        let at = match self.current() {
+            Some(info) => format!("line {}: {}", info.line, info.word),
+            None => String::new(),
+        };
+        match stop {
+            Stop::Step => format!("next {}", at),
+            Stop::Breakpoint(k) => format!("breakpoint {}, next {}", k, at),
+            Stop::Finished => "block finished".to_string(),
+        }
+    }
+}
+
+fn show_breakpoint(bp: &Breakpoint) -> String {
+    match bp {
+        Breakpoint::Word(w) => format!("word {}", w),
+        Breakpoint::Line(l) => format!("line {}", l),
+    }
+}
+
+// vectors are summarized, NaNs should be easy to spot.
+fn show_item(item: &YjrItem) -> String {
+    match item {
+        YjrItem::S(s) => format!("{:?}", s),
+        YjrItem::N(n) => n.to_string(),
+        YjrItem::V(v) => {
+            let v = v.vec();
+            format!("v[{}] {}", v.size(), show_range(v.data()))
+        },
+        YjrItem::M(m) => {
+            let frames = if m.is_empty() { 0 } else { m[0].vec().size() };
+            let ranges: Vec<String> = m.iter().map(|v| show_range(v.vec().data())).collect();
+            format!("m[{}x{}] {}", m.len(), frames, ranges.join(", "))
+        },
+    }
+}
+
+fn show_range(data: &[TNT]) -> String {
+    if data.iter().any(|x| x.is_nan()) {
+        return "has NaN".to_string();
+    }
+    let min = data.iter().cloned().fold(TNT::INFINITY, TNT::min);
+    let max = data.iter().cloned().fold(TNT::NEG_INFINITY, TNT::max);
+    format!("{} .. {}", min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    use crate::runtime::{YjrEnviroment, YjrItem};
+    use crate::debug::{Breakpoint, Stop, Trace};
+
+    #[test]
+    fn step_words() {
+        let mut env = YjrEnviroment::new(41100);
+        let txt = "#def twice 2 * #end
+                   1 2 + twice";
+        let mut rt = env.build(txt);
+        let mut dbg = rt.debugger();
+
+        assert_eq!(dbg.current(), None);
+        assert_eq!(dbg.step(), Stop::Step);
+        assert_eq!(dbg.current().unwrap().word, "2");
+        assert_eq!(dbg.step(), Stop::Step);
+        assert_eq!(dbg.step(), Stop::Step);
+        assert_eq!(dbg.stack().size(), 1);
+
+        // entering user word
+        assert_eq!(dbg.step(), Stop::Step);
+        assert_eq!(dbg.backtrace().len(), 2);
+        assert_eq!(dbg.backtrace()[1].0, "twice");
+        assert_eq!(dbg.step(), Stop::Step);
+        assert_eq!(dbg.step(), Stop::Finished);
+        assert_eq!(dbg.stack().size(), 1);
+        assert_eq!(dbg.stack().top(), &YjrItem::N(6.0));
+    }
+
+    #[test]
+    fn break_and_continue() {
+        let mut env = YjrEnviroment::new(41100);
+        let txt = "64 440 dsp.os.osc
+                   0.5 swap *
+                   64 zeros~ +";
+        let mut rt = env.build(txt);
+        let mut dbg = rt.debugger();
+        dbg.add_breakpoint( Breakpoint::Word("*".to_string()) );
+        dbg.add_breakpoint( Breakpoint::Line(3) );
+
+        assert_eq!(dbg.cont(), Stop::Breakpoint(0));
+        assert_eq!(dbg.stack().size(), 2);
+        assert_eq!(dbg.cont(), Stop::Breakpoint(1));
+        assert_eq!(dbg.current().unwrap().word, "64");
+        assert_eq!(dbg.cont(), Stop::Breakpoint(1));
+        assert_eq!(dbg.current().unwrap().word, "zeros~");
+        assert_eq!(dbg.cont(), Stop::Breakpoint(1));
+        assert_eq!(dbg.cont(), Stop::Finished);
+        assert_eq!(dbg.stack().size(), 1);
+
+        // next block
+        assert_eq!(dbg.cont(), Stop::Breakpoint(0));
+    }
+
+    #[test]
+    fn commands() {
+        let mut env = YjrEnviroment::new(41100);
+        let txt = "3 $x !
+                   $x @ 4 ones~";
+        let mut rt = env.build(txt);
+        let mut dbg = rt.debugger();
+
+        assert_eq!(dbg.command("break ones~"), "breakpoint 0: word ones~");
+        assert_eq!(dbg.command("s 3"), "next line 2: $x");
+        assert_eq!(dbg.command("hash"), "hash of `main`\n$x = 3");
+        assert_eq!(dbg.command("c"), "breakpoint 0, next line 2: ones~");
+        assert_eq!(dbg.command("stack"), "<2>\n3\n4");
+        assert_eq!(dbg.command("step"), "block finished");
+        assert_eq!(dbg.command("stack"), "<2>\n3\nv[4] 1 .. 1");
+        assert_eq!(dbg.command("delete 0"), "breakpoint 0 deleted");
+        assert_eq!(dbg.command("foo"), "unknown command 'foo'");
+    }
+
+    #[test]
+    fn repl_lines() {
+        let mut env = YjrEnviroment::new(41100);
+        let mut rt = env.build("1 2 +");
+        let mut dbg = rt.debugger();
+
+        let mut out = Vec::new();
+        dbg.repl("s 2\n\nstack\nquit\nstack\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "(yjr) next line 1: +\n(yjr) (yjr) <2>\n1\n2\n(yjr) ");
+    }
+
+    #[test]
+    fn trace_hook() {
+        let mut env = YjrEnviroment::new(41100);
+        let mut rt = env.build("1 2 + 3 * sin");
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let log_ = log.clone();
+        rt.set_trace( Box::new(move |t, stack| {
+            let s = match t {
+                Trace::Before(info) => format!("> {} {}", info.word, stack.size()),
+                Trace::After(info) => format!("< {} {}", info.word, stack.size()),
+            };
+            log_.borrow_mut().push(s);
+        }));
+        rt.run();
+        assert_eq!(*log.borrow(), vec!["> + 2", "< + 1", "> * 2", "< * 1", "> sin 1", "< sin 1"]);
+    }
+}
@@ -1,2 +1,2 @@
-pub mod OsOsc;
-pub mod NoNoise;
+// modules of src/faust/auto, generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/faust_auto.rs"));
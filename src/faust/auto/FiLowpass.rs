@@ -0,0 +1,155 @@
+// auto generated files, don't edit it.
+
+#![allow(unused_parens)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+#![allow(non_upper_case_globals)]
+#![allow(clippy::all)]
+
+use crate::faust::faust_help::*;
+
+
+fn dsp_faustpower2_f(value: F32) -> F32 {
+	return value * value;
+}
+pub struct dsp {
+	fSampleRate: i32,
+	fConst0: F32,
+	fHslider0: F32,
+	fRec0: [F32;3],
+}
+
+impl FaustDsp for dsp {
+	type T = F32;
+
+	fn new() -> dsp {
+		dsp {
+			fSampleRate: 0,
+			fConst0: 0.0,
+			fHslider0: 0.0,
+			fRec0: [0.0;3],
+		}
+	}
+	fn metadata(&self, m: &mut dyn Meta) {
+		m.declare("filename", "fi.lowpass.dsp");
+		m.declare("filters.lib/fir:author", "Julius O. Smith III");
+		m.declare("filters.lib/fir:copyright", "Copyright (C) 2003-2019 by Julius O. Smith III <jos@ccrma.stanford.edu>");
+		m.declare("filters.lib/fir:license", "MIT-style STK-4.3 license");
+		m.declare("filters.lib/iir:author", "Julius O. Smith III");
+		m.declare("filters.lib/iir:copyright", "Copyright (C) 2003-2019 by Julius O. Smith III <jos@ccrma.stanford.edu>");
+		m.declare("filters.lib/iir:license", "MIT-style STK-4.3 license");
+		m.declare("filters.lib/lowpass0_highpass1", "MIT-style STK-4.3 license");
+		m.declare("filters.lib/lowpass0_highpass1:author", "Julius O. Smith III");
+		m.declare("filters.lib/lowpass:author", "Julius O. Smith III");
+		m.declare("filters.lib/lowpass:copyright", "Copyright (C) 2003-2019 by Julius O. Smith III <jos@ccrma.stanford.edu>");
+		m.declare("filters.lib/lowpass:license", "MIT-style STK-4.3 license");
+		m.declare("filters.lib/name", "Faust Filters Library");
+		m.declare("filters.lib/tf2:author", "Julius O. Smith III");
+		m.declare("filters.lib/tf2:copyright", "Copyright (C) 2003-2019 by Julius O. Smith III <jos@ccrma.stanford.edu>");
+		m.declare("filters.lib/tf2:license", "MIT-style STK-4.3 license");
+		m.declare("filters.lib/tf2s:author", "Julius O. Smith III");
+		m.declare("filters.lib/tf2s:copyright", "Copyright (C) 2003-2019 by Julius O. Smith III <jos@ccrma.stanford.edu>");
+		m.declare("filters.lib/tf2s:license", "MIT-style STK-4.3 license");
+		m.declare("filters.lib/version", "0.3");
+		m.declare("maths.lib/author", "GRAME");
+		m.declare("maths.lib/copyright", "GRAME");
+		m.declare("maths.lib/license", "LGPL with exception");
+		m.declare("maths.lib/name", "Faust Math Library");
+		m.declare("maths.lib/version", "2.5");
+		m.declare("name", "fi.lowpass");
+		m.declare("platform.lib/name", "Generic Platform Library");
+		m.declare("platform.lib/version", "0.2");
+	}
+
+	fn get_sample_rate(&self) -> i32 {
+		return self.fSampleRate;
+	}
+	fn get_num_inputs(&self) -> i32 {
+		return 1;
+	}
+	fn get_num_outputs(&self) -> i32 {
+		return 1;
+	}
+
+	fn class_init(sample_rate: i32) {
+	}
+	fn instance_reset_params(&mut self) {
+		self.fHslider0 = 1e+03;
+	}
+	fn instance_clear(&mut self) {
+		for l0 in 0..3 {
+			self.fRec0[(l0) as usize] = 0.0;
+		}
+	}
+	fn instance_constants(&mut self, sample_rate: i32) {
+		self.fSampleRate = sample_rate;
+		self.fConst0 = 3.1415927 / F32::min(1.92e+05, F32::max(1.0, ((self.fSampleRate) as F32)));
+	}
+	fn instance_init(&mut self, sample_rate: i32) {
+		self.instance_constants(sample_rate);
+		self.instance_reset_params();
+		self.instance_clear();
+	}
+	fn init(&mut self, sample_rate: i32) {
+		dsp::class_init(sample_rate);
+		self.instance_init(sample_rate);
+	}
+
+	fn build_user_interface(&self, ui_interface: &mut dyn UI<Self::T>) {
+		Self::build_user_interface_static(ui_interface);
+	}
+
+	fn build_user_interface_static(ui_interface: &mut dyn UI<Self::T>) {
+		ui_interface.open_vertical_box("fi.lowpass");
+		ui_interface.declare(Some(ParamIndex(0)), "unit", "Hz");
+		ui_interface.add_horizontal_slider("cutoff", ParamIndex(0), 1e+03, 2e+01, 2e+04, 1.0);
+		ui_interface.close_box();
+	}
+
+	fn get_param(&self, param: ParamIndex) -> Option<Self::T> {
+		match param.0 {
+			0 => Some(self.fHslider0),
+			_ => None,
+		}
+	}
+
+	fn set_param(&mut self, param: ParamIndex, value: Self::T) {
+		match param.0 {
+			0 => { self.fHslider0 = value }
+			_ => {}
+		}
+	}
+
+	fn compute(&mut self, count: i32, inputs: &[&[Self::T]], outputs: &mut[&mut[Self::T]]) {
+		let (inputs0) = if let [inputs0, ..] = inputs {
+			let inputs0 = inputs0[..count as usize].iter();
+			(inputs0)
+		} else {
+			panic!("wrong number of inputs");
+		};
+		let (outputs0) = if let [outputs0, ..] = outputs {
+			let outputs0 = outputs0[..count as usize].iter_mut();
+			(outputs0)
+		} else {
+			panic!("wrong number of outputs");
+		};
+		let mut fSlow0: F32 = F32::tan(self.fConst0 * self.fHslider0);
+		let mut fSlow1: F32 = 1.0 / fSlow0;
+		let mut fSlow2: F32 = 1.0 / ((fSlow1 + 1.4142135) / fSlow0 + 1.0);
+		let mut fSlow3: F32 = (fSlow1 + -1.4142135) / fSlow0 + 1.0;
+		let mut fSlow4: F32 = 2.0 * (1.0 - 1.0 / dsp_faustpower2_f(fSlow0));
+		let zipped_iterators = inputs0.zip(outputs0);
+		for (input0, output0) in zipped_iterators {
+			let mut fTemp0: F32 = *input0;
+			self.fRec0[0] = fTemp0 - fSlow2 * (fSlow3 * self.fRec0[2] + fSlow4 * self.fRec0[1]);
+			*output0 = fSlow2 * (self.fRec0[2] + self.fRec0[0] + 2.0 * self.fRec0[1]);
+			self.fRec0[2] = self.fRec0[1];
+			self.fRec0[1] = self.fRec0[0];
+		}
+	}
+
+}
+
@@ -7,11 +7,14 @@
 #![allow(unused_variables)]
 #![allow(unused_mut)]
 #![allow(non_upper_case_globals)]
+#![allow(clippy::all)]
 
 use crate::faust::faust_help::*;
 
 
 pub struct dsp {
+	fEntry0: F32,
+	iVec0: [i32;2],
 	iRec0: [i32;2],
 	fSampleRate: i32,
 }
@@ -21,15 +24,22 @@ impl FaustDsp for dsp {
 
 	fn new() -> dsp {
 		dsp {
+			fEntry0: 0.0,
+			iVec0: [0;2],
 			iRec0: [0;2],
 			fSampleRate: 0,
 		}
 	}
 	fn metadata(&self, m: &mut dyn Meta) {
 		m.declare("filename", "no.noise.dsp");
+		m.declare("maths.lib/author", "GRAME");
+		m.declare("maths.lib/copyright", "GRAME");
+		m.declare("maths.lib/license", "LGPL with exception");
+		m.declare("maths.lib/name", "Faust Math Library");
+		m.declare("maths.lib/version", "2.5");
 		m.declare("name", "no.noise");
-		m.declare("noises.lib/name", "Faust Noise Generator Library");
-		m.declare("noises.lib/version", "0.4");
+		m.declare("platform.lib/name", "Generic Platform Library");
+		m.declare("platform.lib/version", "0.2");
 	}
 
 	fn get_sample_rate(&self) -> i32 {
@@ -45,10 +55,14 @@ impl FaustDsp for dsp {
 	fn class_init(sample_rate: i32) {
 	}
 	fn instance_reset_params(&mut self) {
+		self.fEntry0 = 0.0;
 	}
 	fn instance_clear(&mut self) {
 		for l0 in 0..2 {
-			self.iRec0[(l0) as usize] = 0;
+			self.iVec0[(l0) as usize] = 0;
+		}
+		for l1 in 0..2 {
+			self.iRec0[(l1) as usize] = 0;
 		}
 	}
 	fn instance_constants(&mut self, sample_rate: i32) {
@@ -70,17 +84,21 @@ impl FaustDsp for dsp {
 
 	fn build_user_interface_static(ui_interface: &mut dyn UI<Self::T>) {
 		ui_interface.open_vertical_box("no.noise");
+		ui_interface.declare(Some(ParamIndex(0)), "seed", "");
+		ui_interface.add_num_entry("seed", ParamIndex(0), 0.0, 0.0, 16777215.0, 1.0);
 		ui_interface.close_box();
 	}
 
 	fn get_param(&self, param: ParamIndex) -> Option<Self::T> {
 		match param.0 {
+			0 => Some(self.fEntry0),
 			_ => None,
 		}
 	}
 
 	fn set_param(&mut self, param: ParamIndex, value: Self::T) {
 		match param.0 {
+			0 => { self.fEntry0 = value }
 			_ => {}
 		}
 	}
@@ -92,10 +110,13 @@ impl FaustDsp for dsp {
 		} else {
 			panic!("wrong number of outputs");
 		};
+		let mut iSlow0: i32 = ((self.fEntry0) as i32);
 		let zipped_iterators = outputs0;
 		for output0 in zipped_iterators {
-			self.iRec0[0] = i32::wrapping_add(i32::wrapping_mul(1103515245, self.iRec0[1]), 12345);
+			self.iVec0[0] = 1;
+			self.iRec0[0] = i32::wrapping_add(i32::wrapping_add(i32::wrapping_mul(1103515245, self.iRec0[1]), 12345), i32::wrapping_mul(iSlow0, i32::wrapping_sub(1, self.iVec0[1])));
 			*output0 = 4.656613e-10 * ((self.iRec0[0]) as F32);
+			self.iVec0[1] = self.iVec0[0];
 			self.iRec0[1] = self.iRec0[0];
 		}
 	}
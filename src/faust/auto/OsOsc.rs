@@ -7,6 +7,7 @@
 #![allow(unused_variables)]
 #![allow(unused_mut)]
 #![allow(non_upper_case_globals)]
+#![allow(clippy::all)]
 
 use crate::faust::faust_help::*;
 
@@ -54,7 +55,7 @@ pub fn newdspSIG0() -> dspSIG0 {
 		iRec0: [0;2],
 	}
 }
-static mut ftbl0dspSIG0: [F32;65536] = [0.0;65536];
+static ftbl0dspSIG0: ClassTable<F32, 65536> = ClassTable::new();
 pub struct dsp {
 	fHslider0: F32,
 	fSampleRate: i32,
@@ -102,7 +103,7 @@ impl FaustDsp for dsp {
 	fn class_init(sample_rate: i32) {
 		let mut sig0: dspSIG0 = newdspSIG0();
 		sig0.instance_initdspSIG0(sample_rate);
-		sig0.filldspSIG0(65536, unsafe { &mut ftbl0dspSIG0 });
+		ftbl0dspSIG0.init(|table| sig0.filldspSIG0(65536, table));
 	}
 	fn instance_reset_params(&mut self) {
 		self.fHslider0 = 4.4e+02;
@@ -161,7 +162,7 @@ impl FaustDsp for dsp {
 		let zipped_iterators = outputs0;
 		for output0 in zipped_iterators {
 			self.fRec1[0] = fSlow0 + (self.fRec1[1] - F32::floor(fSlow0 + self.fRec1[1]));
-			*output0 = unsafe { ftbl0dspSIG0[(((65536.0 * self.fRec1[0]) as i32)) as usize] };
+			*output0 = ftbl0dspSIG0[(((65536.0 * self.fRec1[0]) as i32)) as usize];
 			self.fRec1[1] = self.fRec1[0];
 		}
 	}
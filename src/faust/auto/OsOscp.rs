@@ -0,0 +1,125 @@
+// auto generated files, don't edit it.
+
+#![allow(unused_parens)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+#![allow(non_upper_case_globals)]
+#![allow(clippy::all)]
+
+use crate::faust::faust_help::*;
+
+
+pub struct dsp {
+	fSampleRate: i32,
+	fConst0: F32,
+	fRec0: [F32;2],
+}
+
+impl FaustDsp for dsp {
+	type T = F32;
+
+	fn new() -> dsp {
+		dsp {
+			fSampleRate: 0,
+			fConst0: 0.0,
+			fRec0: [0.0;2],
+		}
+	}
+	fn metadata(&self, m: &mut dyn Meta) {
+		m.declare("basics.lib/name", "Faust Basic Element Library");
+		m.declare("basics.lib/version", "0.8");
+		m.declare("filename", "os.oscp.dsp");
+		m.declare("maths.lib/author", "GRAME");
+		m.declare("maths.lib/copyright", "GRAME");
+		m.declare("maths.lib/license", "LGPL with exception");
+		m.declare("maths.lib/name", "Faust Math Library");
+		m.declare("maths.lib/version", "2.5");
+		m.declare("name", "os.oscp");
+		m.declare("oscillators.lib/name", "Faust Oscillator Library");
+		m.declare("oscillators.lib/version", "0.3");
+		m.declare("platform.lib/name", "Generic Platform Library");
+		m.declare("platform.lib/version", "0.2");
+	}
+
+	fn get_sample_rate(&self) -> i32 {
+		return self.fSampleRate;
+	}
+	fn get_num_inputs(&self) -> i32 {
+		return 2;
+	}
+	fn get_num_outputs(&self) -> i32 {
+		return 1;
+	}
+
+	fn class_init(sample_rate: i32) {
+	}
+	fn instance_reset_params(&mut self) {
+	}
+	fn instance_clear(&mut self) {
+		for l0 in 0..2 {
+			self.fRec0[(l0) as usize] = 0.0;
+		}
+	}
+	fn instance_constants(&mut self, sample_rate: i32) {
+		self.fSampleRate = sample_rate;
+		self.fConst0 = 1.0 / F32::min(1.92e+05, F32::max(1.0, ((self.fSampleRate) as F32)));
+	}
+	fn instance_init(&mut self, sample_rate: i32) {
+		self.instance_constants(sample_rate);
+		self.instance_reset_params();
+		self.instance_clear();
+	}
+	fn init(&mut self, sample_rate: i32) {
+		dsp::class_init(sample_rate);
+		self.instance_init(sample_rate);
+	}
+
+	fn build_user_interface(&self, ui_interface: &mut dyn UI<Self::T>) {
+		Self::build_user_interface_static(ui_interface);
+	}
+
+	fn build_user_interface_static(ui_interface: &mut dyn UI<Self::T>) {
+		ui_interface.open_vertical_box("os.oscp");
+		ui_interface.close_box();
+	}
+
+	fn get_param(&self, param: ParamIndex) -> Option<Self::T> {
+		match param.0 {
+			_ => None,
+		}
+	}
+
+	fn set_param(&mut self, param: ParamIndex, value: Self::T) {
+		match param.0 {
+			_ => {}
+		}
+	}
+
+	fn compute(&mut self, count: i32, inputs: &[&[Self::T]], outputs: &mut[&mut[Self::T]]) {
+		let (inputs0, inputs1) = if let [inputs0, inputs1, ..] = inputs {
+			let inputs0 = inputs0[..count as usize].iter();
+			let inputs1 = inputs1[..count as usize].iter();
+			(inputs0, inputs1)
+		} else {
+			panic!("wrong number of inputs");
+		};
+		let (outputs0) = if let [outputs0, ..] = outputs {
+			let outputs0 = outputs0[..count as usize].iter_mut();
+			(outputs0)
+		} else {
+			panic!("wrong number of outputs");
+		};
+		let zipped_iterators = inputs0.zip(inputs1).zip(outputs0);
+		for ((input0, input1), output0) in zipped_iterators {
+			let mut fTemp0: F32 = self.fConst0 * *input0;
+			self.fRec0[0] = fTemp0 + (self.fRec0[1] - F32::floor(fTemp0 + self.fRec0[1]));
+			*output0 = F32::sin(6.2831855 * self.fRec0[0] + *input1);
+			self.fRec0[1] = self.fRec0[0];
+		}
+	}
+
+}
+
@@ -0,0 +1,3 @@
+import("stdfaust.lib");
+
+process = fi.lowpass(2, hslider("cutoff[unit:Hz]", 1000, 20, 20000, 1));
@@ -1,3 +1,5 @@
 import("stdfaust.lib");
 
-process = no.noise;
+// no.noise with the seed added to the generator state at the first sample
+seed = nentry("seed[seed]", 0, 0, 16777215, 1) : int;
+process = (+(12345 + seed * (1 - 1')) ~ *(1103515245)) / ma.INTMAX;
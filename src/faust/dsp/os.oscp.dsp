@@ -0,0 +1,4 @@
+import("stdfaust.lib");
+
+// sine with per-sample frequency and phase modulation in radians
+process(freq, phase) = sin(2 * ma.PI * os.phasor(1, freq) + phase);
@@ -0,0 +1,4 @@
+import("stdfaust.lib");
+
+// loops the first part of a sound
+process = so.loop(soundfile("sound[url:{'sound.wav'}]", 1), 0);
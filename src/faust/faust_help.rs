@@ -6,19 +6,31 @@
 #![allow(unused_mut)]
 #![allow(non_upper_case_globals)]
 
+// generated code holds soundfiles as Rc<Soundfile>
+pub use std::rc::Rc;
 
 pub type F32 = f32;
 pub type F64 = f64;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct ParamIndex(pub i32);
 
-pub struct Soundfile<'a> {
-    fBuffers: &'a &'a F32,
-    fLength: &'a i32,
-    fSR: &'a i32,
-    fOffset: &'a i32,
-    fChannels: i32,
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SoundfileIndex(pub i32);
+
+/// Sound of a `soundfile` primitive, as in the Faust architecture files.
+///
+/// Parts are concatenated in `fBuffers`, one buffer for each channel. Part
+/// `i` starts at `fOffset[i]` and has `fLength[i]` frames, unused parts up
+/// to `MAX_SOUNDFILE_PARTS` are silent.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Soundfile {
+    pub fBuffers: Vec<Vec<F32>>,
+    pub fLength: Vec<i32>,
+    pub fSR: Vec<i32>,
+    pub fOffset: Vec<i32>,
+    pub fChannels: i32,
+    pub fParts: i32,
 }
 
 pub trait FaustDsp {
@@ -47,6 +59,9 @@ pub trait FaustDsp {
     fn set_param(&mut self, param: ParamIndex, value: Self::T);
     fn compute(&mut self, count: i32, inputs: &[&[Self::T]], outputs: &mut [&mut [Self::T]]);
 
+    // sound of an `add_soundfile` of the ui, dsps without one ignore it
+    fn set_soundfile(&mut self, sf: SoundfileIndex, soundfile: Rc<Soundfile>) {}
+
     // NOTE:
     // these seem to be created by the faust codegen,
     // but are not present here? weird, investigate.
@@ -97,6 +112,68 @@ pub trait UI<T> {
     fn add_horizontal_bargraph(&mut self, label: &str, param: ParamIndex, min: T, max: T);
     fn add_vertical_bargraph(&mut self, label: &str, param: ParamIndex, min: T, max: T);
 
+    // -- soundfiles
+    fn add_soundfile(&mut self, label: &str, url: &str, sf: SoundfileIndex);
+
     // -- metadata declarations
     fn declare(&mut self, param: Option<ParamIndex>, key: &str, value: &str);
 }
+
+/// Class-level table of generated code, filled once by the first
+/// `class_init` and shared by every instance and thread.
+pub struct ClassTable<T, const N: usize> {
+    cell: std::sync::OnceLock<Box<[T]>>,
+}
+
+impl<T: Copy + Default, const N: usize> ClassTable<T, N> {
+    pub const fn new() -> Self {
+        ClassTable {
+            cell: std::sync::OnceLock::new(),
+        }
+    }
+
+    pub fn init<F: FnOnce(&mut [T])>(&self, fill: F) {
+        self.cell.get_or_init(|| {
+            let mut table = vec![T::default(); N].into_boxed_slice();
+            fill(&mut table);
+            table
+        });
+    }
+}
+
+impl<T, const N: usize> std::ops::Index<usize> for ClassTable<T, N> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        match self.cell.get() {
+            Some(table) => &table[i],
+            None => panic!("Faust class table is used before class_init"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::faust::faust_help::ClassTable;
+
+    #[test]
+    fn class_table() {
+        static TABLE: ClassTable<f32, 4> = ClassTable::new();
+        static FILLS: AtomicUsize = AtomicUsize::new(0);
+
+        let threads: Vec<_> = (0..8).map(|_| std::thread::spawn(|| {
+            TABLE.init(|t| {
+                FILLS.fetch_add(1, Ordering::SeqCst);
+                for (i, x) in t.iter_mut().enumerate() {
+                    *x = i as f32;
+                }
+            });
+            TABLE[3]
+        })).collect();
+        for t in threads {
+            assert_eq!(t.join().unwrap(), 3.0);
+        }
+        assert_eq!(FILLS.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,257 @@
+//! Description of a dsp as JSON, in the shape of the Faust JSON UI.
+//!
+//! Parameter `index` is the `ParamIndex` of the control, not a memory
+//! offset as in Faust.
+
+use crate::TNT;
+use crate::faust::faust_help::{FaustDsp, Meta, ParamIndex, SoundfileIndex, UI};
+
+fn quote(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// [ { "key": "value" }, ... ]
+fn meta_list(entries: &[(String, String)]) -> String {
+    let items: Vec<String> = entries.iter().map(|(k, v)| format!("{{ {}: {} }}", quote(k), quote(v))).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Collects `declare`s of `metadata()`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MetaJson {
+    pub entries: Vec<(String, String)>,
+}
+
+impl MetaJson {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    pub fn json(&self) -> String {
+        meta_list(&self.entries)
+    }
+}
+
+impl Meta for MetaJson {
+    fn declare(&mut self, key: &str, value: &str) {
+        self.entries.push( (key.to_string(), value.to_string()) );
+    }
+}
+
+// a box in construction
+struct Group {
+    kind: &'static str,
+    label: String,
+    meta: Vec<(String, String)>,
+    items: Vec<String>,
+}
+
+/// Builds the `ui` tree of boxes and widgets.
+#[derive(Default)]
+pub struct UiJson {
+    groups: Vec<Group>,
+    path: Vec<String>,
+    items: Vec<String>,                     // top level boxes
+    box_meta: Vec<(String, String)>,        // declares of the next box
+    pending: Vec<(i32, String, String)>,    // declares of widgets
+}
+
+impl UiJson {
+    pub fn json(&self) -> String {
+        format!("[{}]", self.items.join(", "))
+    }
+
+    fn open(&mut self, kind: &'static str, label: &str) {
+        self.groups.push( Group {
+            kind,
+            label: label.to_string(),
+            meta: std::mem::take(&mut self.box_meta),
+            items: Vec::new(),
+        });
+        self.path.push(label.to_string());
+    }
+
+    fn push(&mut self, item: String) {
+        match self.groups.last_mut() {
+            Some(g) => g.items.push(item),
+            None => self.items.push(item),
+        }
+    }
+
+    fn address(&self, label: &str) -> String {
+        let mut address = String::new();
+        for l in self.path.iter().chain(std::iter::once(&label.to_string())) {
+            address.push('/');
+            address.push_str(l);
+        }
+        address
+    }
+
+    fn widget(&mut self, kind: &str, label: &str, param: ParamIndex, range: &[(&str, TNT)]) {
+        let address = self.address(label);
+        let meta: Vec<(String, String)> = self.pending.iter()
+            .filter(|(i, _, _)| *i == param.0)
+            .map(|(_, k, v)| (k.clone(), v.clone()))
+            .collect();
+        self.pending.retain(|(i, _, _)| *i != param.0);
+
+        let mut fields = vec![
+            format!("\"type\": {}", quote(kind)),
+            format!("\"label\": {}", quote(label)),
+            format!("\"address\": {}", quote(&address)),
+            format!("\"index\": {}", param.0),
+        ];
+        if !meta.is_empty() {
+            fields.push( format!("\"meta\": {}", meta_list(&meta)) );
+        }
+        for (k, v) in range {
+            fields.push( format!("{}: {}", quote(k), v) );
+        }
+        self.push( format!("{{ {} }}", fields.join(", ")) );
+    }
+}
+
+impl UI<TNT> for UiJson {
+    fn open_tab_box(&mut self, label: &str) {
+        self.open("tgroup", label);
+    }
+    fn open_horizontal_box(&mut self, label: &str) {
+        self.open("hgroup", label);
+    }
+    fn open_vertical_box(&mut self, label: &str) {
+        self.open("vgroup", label);
+    }
+    fn close_box(&mut self) {
+        if let Some(g) = self.groups.pop() {
+            self.path.pop();
+            let mut fields = vec![
+                format!("\"type\": {}", quote(g.kind)),
+                format!("\"label\": {}", quote(&g.label)),
+            ];
+            if !g.meta.is_empty() {
+                fields.push( format!("\"meta\": {}", meta_list(&g.meta)) );
+            }
+            fields.push( format!("\"items\": [{}]", g.items.join(", ")) );
+            self.push( format!("{{ {} }}", fields.join(", ")) );
+        }
+    }
+
+    fn add_button(&mut self, label: &str, param: ParamIndex) {
+        self.widget("button", label, param, &[]);
+    }
+    fn add_check_button(&mut self, label: &str, param: ParamIndex) {
+        self.widget("checkbox", label, param, &[]);
+    }
+    fn add_vertical_slider(&mut self, label: &str, param: ParamIndex, init: TNT, min: TNT, max: TNT, step: TNT) {
+        self.widget("vslider", label, param, &[("init", init), ("min", min), ("max", max), ("step", step)]);
+    }
+    fn add_horizontal_slider(&mut self, label: &str, param: ParamIndex, init: TNT, min: TNT, max: TNT, step: TNT) {
+        self.widget("hslider", label, param, &[("init", init), ("min", min), ("max", max), ("step", step)]);
+    }
+    fn add_num_entry(&mut self, label: &str, param: ParamIndex, init: TNT, min: TNT, max: TNT, step: TNT) {
+        self.widget("nentry", label, param, &[("init", init), ("min", min), ("max", max), ("step", step)]);
+    }
+
+    fn add_horizontal_bargraph(&mut self, label: &str, param: ParamIndex, min: TNT, max: TNT) {
+        self.widget("hbargraph", label, param, &[("min", min), ("max", max)]);
+    }
+    fn add_vertical_bargraph(&mut self, label: &str, param: ParamIndex, min: TNT, max: TNT) {
+        self.widget("vbargraph", label, param, &[("min", min), ("max", max)]);
+    }
+
+    fn add_soundfile(&mut self, label: &str, url: &str, sf: SoundfileIndex) {
+        let item = format!("{{ \"type\": \"soundfile\", \"label\": {}, \"url\": {}, \"address\": {}, \"index\": {} }}",
+                           quote(label), quote(url), quote(&self.address(label)), sf.0);
+        self.push(item);
+    }
+
+    fn declare(&mut self, param: Option<ParamIndex>, key: &str, value: &str) {
+        match param {
+            Some(p) => self.pending.push( (p.0, key.to_string(), value.to_string()) ),
+            None => self.box_meta.push( (key.to_string(), value.to_string()) ),
+        }
+    }
+}
+
+/// Full description of a dsp: name, filename, inputs, outputs, metadata
+/// and ui.
+pub fn describe<D: FaustDsp<T = TNT>>() -> String {
+    let dsp = D::new();
+    let mut meta = MetaJson::default();
+    dsp.metadata(&mut meta);
+    let mut ui = UiJson::default();
+    D::build_user_interface_static(&mut ui);
+
+    format!("{{ \"name\": {}, \"filename\": {}, \"inputs\": {}, \"outputs\": {}, \"meta\": {}, \"ui\": {} }}",
+            quote(meta.get("name").unwrap_or("")),
+            quote(meta.get("filename").unwrap_or("")),
+            dsp.get_num_inputs(),
+            dsp.get_num_outputs(),
+            meta.json(),
+            ui.json())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::faust::auto::*;
+    use crate::faust::words::soloop;
+    use crate::faust::faust_help::{ParamIndex, UI};
+    use crate::faust::json::{describe, quote, UiJson};
+
+    #[test]
+    fn strings() {
+        assert_eq!(quote("a\"b\\c\n"), "\"a\\\"b\\\\c\\n\"");
+        assert_eq!(quote("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn ui_tree() {
+        let mut ui = UiJson::default();
+        ui.declare(None, "tooltip", "a synth");
+        ui.open_vertical_box("synth");
+        ui.declare(Some(ParamIndex(1)), "unit", "Hz");
+        ui.add_horizontal_slider("freq", ParamIndex(1), 440.0, 20.0, 2000.0, 0.5);
+        ui.open_horizontal_box("env");
+        ui.add_button("gate", ParamIndex(0));
+        ui.close_box();
+        ui.add_vertical_bargraph("level", ParamIndex(2), -60.0, 0.0);
+        ui.close_box();
+
+        assert_eq!(ui.json(), concat!(
+            "[{ \"type\": \"vgroup\", \"label\": \"synth\", \"meta\": [{ \"tooltip\": \"a synth\" }], \"items\": [",
+            "{ \"type\": \"hslider\", \"label\": \"freq\", \"address\": \"/synth/freq\", \"index\": 1, ",
+            "\"meta\": [{ \"unit\": \"Hz\" }], \"init\": 440, \"min\": 20, \"max\": 2000, \"step\": 0.5 }, ",
+            "{ \"type\": \"hgroup\", \"label\": \"env\", \"items\": [",
+            "{ \"type\": \"button\", \"label\": \"gate\", \"address\": \"/synth/env/gate\", \"index\": 0 }] }, ",
+            "{ \"type\": \"vbargraph\", \"label\": \"level\", \"address\": \"/synth/level\", \"index\": 2, \"min\": -60, \"max\": 0 }] }]"));
+    }
+
+    #[test]
+    fn dsps() {
+        let j = describe::<OsOsc::dsp>();
+        assert!(j.starts_with("{ \"name\": \"os.osc\", \"filename\": \"os.osc.dsp\", \"inputs\": 0, \"outputs\": 1, \"meta\": [{ \"basics.lib/name\": "));
+        assert!(j.contains("\"ui\": [{ \"type\": \"vgroup\", \"label\": \"os.osc\", \"items\": [{ \"type\": \"hslider\", \"label\": \"freq\", \"address\": \"/os.osc/freq\", \"index\": 0, \"init\": 440, \"min\": 25, \"max\": 11000, \"step\": 0.1 }] }]"));
+
+        let j = describe::<NoNoise::dsp>();
+        assert!(j.contains("\"meta\": [{ \"seed\": \"\" }]"));
+
+        let j = describe::<OsOscp::dsp>();
+        assert!(j.contains("\"inputs\": 2, \"outputs\": 1"));
+
+        let j = describe::<soloop::dsp>();
+        assert!(j.contains("\"items\": [{ \"type\": \"soundfile\", \"label\": \"sound\", \"url\": \"{'sound.wav'}\", \"address\": \"/so.loop/sound\", \"index\": 0 }]"));
+    }
+}
@@ -1,11 +1,68 @@
+use crate::TNT;
 use crate::runtime::{YjrEnviroment};
 
 mod faust_help;
 mod auto;
+mod ui;
+mod json;
+mod soundfile;
 mod words;
 
+use faust_help::FaustDsp;
+use words::generic::FaustWord;
+
+// word, stack effect and description of a generated dsp
+fn insert_faust_word<D: FaustDsp<T = TNT> + 'static>(env: &mut YjrEnviroment, name: &str) {
+    env.insert_native_word(name, FaustWord::<D>::new);
+    env.insert_native_effect(name, &FaustWord::<D>::effect());
+    env.insert_faust_description(name, &json::describe::<D>());
+}
+
+// insert_generated_words(), every module of auto as `dsp.<name>`
+include!(concat!(env!("OUT_DIR"), "/faust_words.rs"));
+
 pub fn insert_native_words(env: &mut YjrEnviroment) {
-    env.insert_native_word("dsp.no.noise", words::noises::NoiseWord::new);
-    env.insert_native_word("dsp.os.osc", words::oscillators::OscWord::new);
+    insert_generated_words(env);
+
+    // hand written glue replaces the generic word
+    env.insert_native_word("dsp.os.oscp", words::oscillators::PmOscWord::new);
+    insert_faust_word::<words::soloop::dsp>(env, "dsp.so.loop");
+    env.insert_native_word("param!", words::params::ParamWord::new);
+
+    env.insert_native_effect("dsp.os.oscp", "n n n -- v | n v n -- v | n n v -- v | n v v -- v");
+    env.insert_native_effect("param!", "s n -- | s s --");
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::runtime::YjrEnviroment;
+    use crate::words::testing;
+
+    #[test]
+    fn descriptions() {
+        let env = YjrEnviroment::new(44100);
+        let words = env.faust_words();
+        let names: Vec<&str> = words.iter().map(|(n, _)| *n).collect();
+        assert_eq!(names, vec!["dsp.fi.lowpass", "dsp.no.noise", "dsp.os.osc", "dsp.os.oscp", "dsp.so.loop"]);
+        assert!(words[2].1.starts_with("{ \"name\": \"os.osc\""));
+    }
+
+    fn render(script: &str) -> Vec<TNT> {
+        testing::render(44100, script, 1)
+    }
+
+    #[test]
+    fn threads() {
+        // runtimes with table based dsps built at once
+        let expected = render("64 440 dsp.os.osc");
+        let threads: Vec<_> = (0..8).map(|_| std::thread::spawn(|| {
+            (0..16).map(|_| render("64 440 dsp.os.osc")).collect::<Vec<_>>()
+        })).collect();
+        for t in threads {
+            for y in t.join().unwrap() {
+                assert_eq!(y, expected);
+            }
+        }
+    }
+}
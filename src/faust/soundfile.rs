@@ -0,0 +1,128 @@
+//! Loading of `soundfile` sounds from WAV files.
+//!
+//! An url is a single path or a list of parts as in Faust,
+//! `{'a.wav';'b.wav'}`. Every part is resampled to the sample rate of the
+//! enviroment, so `fSR` is the same for all parts.
+
+use crate::TNT;
+use crate::wav;
+use crate::vector::resample::resample;
+use crate::faust::faust_help::Soundfile;
+
+pub const MAX_SOUNDFILE_PARTS: usize = 256;
+
+// silent frames after the parts, read by unused parts
+const BUFFER_SIZE: usize = 1024;
+
+/// Paths of the parts of an url.
+pub fn parts(url: &str) -> Vec<String> {
+    let url = url.trim();
+    let list = match url.strip_prefix('{').and_then(|u| u.strip_suffix('}')) {
+        Some(list) => list,
+        None => return vec![url.to_string()],
+    };
+    list.split(';')
+        .map(|p| p.trim().trim_matches('\'').to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// Builds a sound of parts, each one channels and its sample rate.
+pub fn build(sounds: &[(Vec<Vec<TNT>>, i32)], sample_rate: i32) -> Soundfile {
+    assert!(sounds.len() <= MAX_SOUNDFILE_PARTS, "Soundfile has more than {} parts", MAX_SOUNDFILE_PARTS);
+    let channels = sounds.iter().map(|(c, _)| c.len()).max().unwrap_or(1).max(1);
+
+    let mut sf = Soundfile {
+        fBuffers: vec![Vec::new(); channels],
+        fLength: Vec::new(),
+        fSR: Vec::new(),
+        fOffset: Vec::new(),
+        fChannels: channels as i32,
+        fParts: sounds.len() as i32,
+    };
+    let mut empty = Vec::new();
+    for (i, (chans, rate)) in sounds.iter().enumerate() {
+        let chans: Vec<Vec<TNT>> = chans.iter().map(|c| resample(c, *rate, sample_rate)).collect();
+        let length = chans.first().map(|c| c.len()).unwrap_or(0);
+        if length == 0 {
+            empty.push(i);
+        }
+        sf.fOffset.push( sf.fBuffers[0].len() as i32 );
+        sf.fLength.push( length as i32 );
+        sf.fSR.push( sample_rate );
+        // parts with less channels repeat theirs
+        for (c, buffer) in sf.fBuffers.iter_mut().enumerate() {
+            match chans.get(c % chans.len().max(1)) {
+                Some(data) => buffer.extend_from_slice(data),
+                None => buffer.resize(buffer.len() + length, 0.0),
+            }
+        }
+    }
+
+    let silence = sf.fBuffers[0].len() as i32;
+    for buffer in sf.fBuffers.iter_mut() {
+        buffer.resize(buffer.len() + BUFFER_SIZE, 0.0);
+    }
+    for i in empty {
+        sf.fOffset[i] = silence;
+        sf.fLength[i] = BUFFER_SIZE as i32;
+    }
+    while sf.fLength.len() < MAX_SOUNDFILE_PARTS {
+        sf.fOffset.push( silence );
+        sf.fLength.push( BUFFER_SIZE as i32 );
+        sf.fSR.push( sample_rate );
+    }
+    sf
+}
+
+/// Loads every part of an url, panics when a file can't be read.
+pub fn load(url: &str, sample_rate: i32) -> Soundfile {
+    let sounds: Vec<(Vec<Vec<TNT>>, i32)> = parts(url).iter().map(|path| {
+        match wav::read(path) {
+            Ok(sound) => sound,
+            Err(e) => panic!("Can't load soundfile '{}': {}", path, e),
+        }
+    }).collect();
+    build(&sounds, sample_rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::faust::soundfile::{parts, build, load, MAX_SOUNDFILE_PARTS};
+
+    #[test]
+    fn urls() {
+        assert_eq!(parts("a.wav"), vec!["a.wav"]);
+        assert_eq!(parts("{'a.wav';'dir/b.wav'}"), vec!["a.wav", "dir/b.wav"]);
+        assert_eq!(parts("{}"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn layout() {
+        let a = (vec![vec![1.0, 2.0, 3.0]], 1000);
+        let b = (vec![vec![4.0, 5.0], vec![6.0, 7.0]], 1000);
+        let empty = (vec![vec![]], 1000);
+        let sf = build(&[a, b, empty], 1000);
+
+        assert_eq!((sf.fChannels, sf.fParts), (2, 3));
+        assert_eq!(sf.fLength.len(), MAX_SOUNDFILE_PARTS);
+        assert_eq!(&sf.fOffset[..4], &[0, 3, 5, 5]);
+        assert_eq!(&sf.fLength[..4], &[3, 2, 1024, 1024]);
+        assert!(sf.fSR.iter().all(|r| *r == 1000));
+        assert_eq!(&sf.fBuffers[0][..5], &[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(&sf.fBuffers[1][..5], &[1.0, 2.0, 3.0, 6.0, 7.0]);
+        assert_eq!(sf.fBuffers[1].len(), 5 + 1024);
+        assert!(sf.fBuffers[1][5..].iter().all(|v| *v == 0.0));
+
+        // resampled to the enviroment
+        let sf = build(&[(vec![vec![0.5; 100]], 500)], 1000);
+        assert_eq!(sf.fLength[0], 200);
+        assert_eq!(sf.fOffset[1], 200);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't load soundfile 'yjriver_no_such_file.wav'")]
+    fn missing() {
+        load("{'yjriver_no_such_file.wav'}", 44100);
+    }
+}
@@ -7,6 +7,7 @@
 #![allow(unused_variables)]
 #![allow(unused_mut)]
 #![allow(non_upper_case_globals)]
+#![allow(clippy::all)]
 
 use crate::faust::faust_help::*;
 
@@ -0,0 +1,246 @@
+use crate::TNT;
+use crate::faust::faust_help::{FaustDsp, ParamIndex, SoundfileIndex, UI};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Widget {
+    Button,
+    CheckButton,
+    VerticalSlider,
+    HorizontalSlider,
+    NumEntry,
+    HorizontalBargraph,
+    VerticalBargraph,
+}
+
+/// A control of a dsp, `path` is the labels of the boxes and the control
+/// joined as in Faust, e.g. `/os.osc/freq`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Param {
+    pub path: String,
+    pub index: ParamIndex,
+    pub widget: Widget,
+    pub init: TNT,
+    pub min: TNT,
+    pub max: TNT,
+    pub step: TNT,
+    pub meta: Vec<(String, String)>,
+}
+
+impl Param {
+    /// Bargraphs are outputs of the dsp.
+    pub fn is_output(&self) -> bool {
+        matches!(self.widget, Widget::HorizontalBargraph | Widget::VerticalBargraph)
+    }
+
+    /// Declared with `[seed]`, set from the enviroment.
+    pub fn is_seed(&self) -> bool {
+        self.meta.iter().any(|(k, _)| k == "seed")
+    }
+
+    pub fn clamp(&self, value: TNT) -> TNT {
+        value.clamp(self.min, self.max)
+    }
+}
+
+/// A `soundfile` of a dsp and the url it declares.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SoundParam {
+    pub path: String,
+    pub index: SoundfileIndex,
+    pub url: String,
+}
+
+/// A name matches the full path, or its last labels. Other characters than
+/// alphanumeric, '_' and '.' in labels are written as '_'. The dsp label of
+/// an instance, see `instance_path`, matches with or without its number,
+/// `os.osc/freq` or `os.osc.2/freq`.
+pub fn path_matches(path: &str, name: &str) -> bool {
+    let label = |l: &str| -> String {
+        l.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '_' }).collect()
+    };
+    let segment = |a: &str, b: &str| match a.split_once('#') {
+        Some((l, k)) => b == label(l) || b == format!("{}.{}", label(l), k),
+        None => b == label(a),
+    };
+    let path: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    let name: Vec<&str> = name.trim_start_matches('/').split('/').collect();
+    name.len() <= path.len() && path[path.len() - name.len()..].iter().zip(name.iter()).all(|(a, b)| segment(a, b))
+}
+
+/// Path of a control in the `k`-th instance of its dsp, `/os.osc#2/freq`
+/// named `os.osc.2/freq` in scripts.
+pub fn instance_path(path: &str, k: usize) -> String {
+    match path.trim_start_matches('/').split_once('/') {
+        Some((dsp, rest)) => format!("/{}#{}/{}", dsp, k, rest),
+        None => format!("{}#{}", path, k),
+    }
+}
+
+/// Parameter table collected from `build_user_interface`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ParamTable {
+    pub params: Vec<Param>,
+    pub sounds: Vec<SoundParam>,
+    boxes: Vec<String>,
+    pending: Vec<(i32, String, String)>,    // declares before their widget
+}
+
+impl ParamTable {
+    pub fn of<D: FaustDsp<T = TNT>>() -> Self {
+        let mut table = ParamTable::default();
+        D::build_user_interface_static(&mut table);
+        table
+    }
+
+    /// Controls taken from the stack by a word, in declared order.
+    pub fn controls(&self) -> Vec<Param> {
+        self.params.iter().filter(|p| !p.is_output() && !p.is_seed()).cloned().collect()
+    }
+
+    pub fn seeds(&self) -> Vec<Param> {
+        self.params.iter().filter(|p| p.is_seed()).cloned().collect()
+    }
+
+    fn path(&self, label: &str) -> String {
+        let mut path = String::new();
+        for b in self.boxes.iter().chain(std::iter::once(&label.to_string())) {
+            path.push('/');
+            path.push_str(b);
+        }
+        path
+    }
+
+    fn add(&mut self, label: &str, index: ParamIndex, widget: Widget, range: [TNT; 4]) {
+        let path = self.path(label);
+        let meta = self.pending.iter()
+            .filter(|(i, _, _)| *i == index.0)
+            .map(|(_, k, v)| (k.clone(), v.clone()))
+            .collect();
+        self.pending.retain(|(i, _, _)| *i != index.0);
+
+        let [init, min, max, step] = range;
+        self.params.push( Param { path, index, widget, init, min, max, step, meta } );
+    }
+}
+
+impl UI<TNT> for ParamTable {
+    fn open_tab_box(&mut self, label: &str) {
+        self.boxes.push(label.to_string());
+    }
+    fn open_horizontal_box(&mut self, label: &str) {
+        self.boxes.push(label.to_string());
+    }
+    fn open_vertical_box(&mut self, label: &str) {
+        self.boxes.push(label.to_string());
+    }
+    fn close_box(&mut self) {
+        self.boxes.pop();
+    }
+
+    fn add_button(&mut self, label: &str, param: ParamIndex) {
+        self.add(label, param, Widget::Button, [0.0, 0.0, 1.0, 1.0]);
+    }
+    fn add_check_button(&mut self, label: &str, param: ParamIndex) {
+        self.add(label, param, Widget::CheckButton, [0.0, 0.0, 1.0, 1.0]);
+    }
+    fn add_vertical_slider(&mut self, label: &str, param: ParamIndex, init: TNT, min: TNT, max: TNT, step: TNT) {
+        self.add(label, param, Widget::VerticalSlider, [init, min, max, step]);
+    }
+    fn add_horizontal_slider(&mut self, label: &str, param: ParamIndex, init: TNT, min: TNT, max: TNT, step: TNT) {
+        self.add(label, param, Widget::HorizontalSlider, [init, min, max, step]);
+    }
+    fn add_num_entry(&mut self, label: &str, param: ParamIndex, init: TNT, min: TNT, max: TNT, step: TNT) {
+        self.add(label, param, Widget::NumEntry, [init, min, max, step]);
+    }
+
+    fn add_horizontal_bargraph(&mut self, label: &str, param: ParamIndex, min: TNT, max: TNT) {
+        self.add(label, param, Widget::HorizontalBargraph, [min, min, max, 0.0]);
+    }
+    fn add_vertical_bargraph(&mut self, label: &str, param: ParamIndex, min: TNT, max: TNT) {
+        self.add(label, param, Widget::VerticalBargraph, [min, min, max, 0.0]);
+    }
+
+    fn add_soundfile(&mut self, label: &str, url: &str, sf: SoundfileIndex) {
+        let path = self.path(label);
+        self.sounds.push( SoundParam { path, index: sf, url: url.to_string() } );
+    }
+
+    fn declare(&mut self, param: Option<ParamIndex>, key: &str, value: &str) {
+        // box declares are not kept
+        if let Some(p) = param {
+            self.pending.push( (p.0, key.to_string(), value.to_string()) );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::faust::auto::*;
+    use crate::faust::words::soloop;
+    use crate::faust::faust_help::{ParamIndex, UI};
+    use crate::faust::ui::{ParamTable, Param, Widget, path_matches, instance_path};
+
+    fn find<'a>(t: &'a ParamTable, name: &str) -> Option<&'a Param> {
+        t.params.iter().find(|p| path_matches(&p.path, name))
+    }
+
+    #[test]
+    fn collect() {
+        let t = ParamTable::of::<OsOsc::dsp>();
+        assert_eq!(t.params.len(), 1);
+        let p = &t.params[0];
+        assert_eq!(p.path, "/os.osc/freq");
+        assert_eq!(p.index, ParamIndex(0));
+        assert_eq!(p.widget, Widget::HorizontalSlider);
+        assert_eq!((p.init, p.min, p.max, p.step), (440.0, 25.0, 11000.0, 0.1));
+        assert_eq!(p.clamp(20000.0), 11000.0);
+        assert!(find(&t, "freq").is_some());
+        assert!(find(&t, "os.osc/freq").is_some());
+        assert!(find(&t, "/os.osc/freq").is_some());
+        assert!(find(&t, "req").is_none());
+
+        let t = ParamTable::of::<NoNoise::dsp>();
+        assert!(t.controls().is_empty());
+        assert_eq!(t.seeds().len(), 1);
+
+        let t = ParamTable::of::<soloop::dsp>();
+        assert!(t.params.is_empty());
+        assert_eq!(t.sounds.len(), 1);
+        assert_eq!(t.sounds[0].path, "/so.loop/sound");
+        assert_eq!(t.sounds[0].url, "{'sound.wav'}");
+    }
+
+    #[test]
+    fn nested() {
+        let mut t = ParamTable::default();
+        t.open_vertical_box("synth");
+        t.open_horizontal_box("env");
+        t.declare(Some(ParamIndex(1)), "unit", "s");
+        t.add_vertical_slider("attack", ParamIndex(1), 0.01, 0.0, 2.0, 0.001);
+        t.close_box();
+        t.add_button("gate", ParamIndex(0));
+        t.add_check_button("hold on", ParamIndex(3));
+        t.add_horizontal_bargraph("level", ParamIndex(2), -60.0, 0.0);
+        t.close_box();
+
+        let paths: Vec<&str> = t.params.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(paths, vec!["/synth/env/attack", "/synth/gate", "/synth/hold on", "/synth/level"]);
+        assert!(find(&t, "hold_on").is_some());
+        assert_eq!(find(&t, "attack").unwrap().meta, vec![("unit".to_string(), "s".to_string())]);
+        assert_eq!(find(&t, "gate").unwrap().meta, vec![]);
+        assert!(find(&t, "env/attack").is_some());
+        assert!(find(&t, "synth/attack").is_none());
+        assert_eq!(t.controls().len(), 3);
+    }
+
+    #[test]
+    fn instances() {
+        let path = instance_path("/os.osc/freq", 2);
+        assert_eq!(path, "/os.osc#2/freq");
+        assert!(path_matches(&path, "freq"));
+        assert!(path_matches(&path, "os.osc/freq"));
+        assert!(path_matches(&path, "os.osc.2/freq"));
+        assert!(!path_matches(&path, "os.osc.1/freq"));
+        assert!(!path_matches(&path, "os.osc#2/freq"));
+    }
+}
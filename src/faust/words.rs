@@ -1,2 +1,4 @@
-pub mod noises;
+pub mod generic;
 pub mod oscillators;
+pub mod params;
+pub mod soloop;
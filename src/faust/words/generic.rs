@@ -0,0 +1,315 @@
+use crate::TNT;
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, SharedVector, NamedParams};
+use crate::words::{output, control_effect, Control};
+
+use crate::faust::faust_help::{FaustDsp, Rc};
+use crate::faust::ui::{Param, ParamTable, SoundParam, instance_path};
+use crate::faust::soundfile;
+
+// channels of a dsp computed one sample at a time
+const MAX_CHANNELS: usize = 16;
+
+// [n | inputs] params sounds dsp.xxx -> outputs
+//
+// Any generated dsp as a word. Generators take a length, effects one vector
+// for each input. Every control follows in declared order, a number or a
+// per-sample vector, then a path or url for each soundfile, and one vector
+// is pushed for each output.
+// Soundfiles are loaded at build, so their paths must be constants right
+// before the word, `null` keeps the declared url.
+// Controls set by name with `param!` win over the stack, controls declared
+// with `[seed]` are set from the enviroment. Instances of a dsp are numbered
+// in the order they are built, `os.osc.2/freq` names the second one only.
+pub struct FaustWord<D> {
+    dsp: D,
+    rate: i32,
+    params: Vec<Param>,
+    sounds: Vec<SoundParam>,
+    named: NamedParams,
+    controls: Vec<Control>,         // scratch of a run
+    ovs: Vec<Option<SharedVector>>,
+}
+
+impl<D: FaustDsp<T = TNT> + 'static> FaustWord<D> {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        let mut dsp = D::new();
+        dsp.init( env.query("SampleRate").0 );
+
+        let table = ParamTable::of::<D>();
+        for s in table.seeds() {
+            // seed entries are floats, keep them exact
+            dsp.set_param( s.index, (env.seed() & 0xffffff) as TNT );
+        }
+        let mut params = table.controls();
+        let params_len = params.len();
+        let sounds = table.sounds.clone();
+        let named = env.params();
+        let mut k = 1;
+        if let Some(p) = params.first() {
+            while named.borrow().contains_key(&instance_path(&p.path, k)) {
+                k += 1;
+            }
+        }
+        for p in params.iter_mut() {
+            p.path = instance_path(&p.path, k);
+            named.borrow_mut().insert(p.path.clone(), None);
+        }
+        let outputs = dsp.get_num_outputs() as usize;
+        if outputs.max(dsp.get_num_inputs() as usize) > MAX_CHANNELS {
+            panic!("Faust word supports at most {} inputs and outputs", MAX_CHANNELS);
+        }
+        Box::new( FaustWord {
+            dsp,
+            rate: env.query("SampleRate").0,
+            params,
+            sounds,
+            named,
+            controls: Vec::with_capacity(params_len),
+            ovs: vec![None; outputs],
+        })
+    }
+
+    /// Stack effect of the word, see `crate::verify`.
+    pub fn effect() -> String {
+        let dsp = D::new();
+        let table = ParamTable::of::<D>();
+        let inputs = match dsp.get_num_inputs() {
+            0 => vec!["n"],
+            n => vec!["v"; n as usize],
+        };
+        let outputs = vec!["v"; dsp.get_num_outputs() as usize].join(" ");
+        let sounds = " s".repeat(table.sounds.len());
+        control_effect(&inputs.join(" "), table.controls().len(), &outputs)
+            .split(" | ")
+            .map(|e| e.replacen(" --", &format!("{} --", sounds), 1))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+impl<D: FaustDsp<T = TNT>> NativeWord for FaustWord<D> {
+    fn link(&mut self, literals: &[YjrItem]) {
+        // paths of the soundfiles are the last constants
+        let paths = literals.len().checked_sub(self.sounds.len()).map_or(&[][..], |k| &literals[k..]);
+        for (i, sound) in self.sounds.iter().enumerate() {
+            let url = match paths.get(i) {
+                Some(YjrItem::S(url)) => url.trim_start_matches(['$', '%']),
+                _ => panic!("Faust soundfile '{}' needs a constant path right before the word", sound.path),
+            };
+            let url = if url.is_empty() { &sound.url } else { url };
+            self.dsp.set_soundfile( sound.index, Rc::new(soundfile::load(url, self.rate)) );
+        }
+    }
+
+    fn run(&mut self, stack: &mut YjrStack) {
+        let inputs = self.dsp.get_num_inputs() as usize;
+        let np = self.params.len();
+        let ns = self.sounds.len();
+        let n = stack.size();
+        if n < np + ns + inputs.max(1) {
+            panic!("Faust word needs {} inputs, {} soundfiles and {} parameters", inputs, ns, np);
+        }
+        let size = match &stack.items()[n - ns - np - 1] {
+            YjrItem::V(v) if inputs > 0 => v.vec().size(),
+            YjrItem::N(c) if inputs == 0 && c.fract() == 0.0 && *c >= 0.0 => *c as usize,
+            _ => panic!("Faust word needs a length or input vectors!"),
+        };
+
+        // soundfiles are loaded by link
+        for _ in 0..ns {
+            stack.pop_string();
+        }
+        let controls = &mut self.controls;
+        controls.clear();
+        for _ in 0..np {
+            controls.push( Control::pop(stack, size) );
+        }
+        controls.reverse();
+        {
+            let named = self.named.borrow();
+            for (p, c) in self.params.iter().zip(controls.iter_mut()) {
+                if let Some(Some(v)) = named.get(&p.path) {
+                    *c = Control::N( p.clamp(*v) );
+                }
+            }
+        }
+        let mut ins: Vec<SharedVector> = Vec::new();
+        if inputs == 0 {
+            stack.pop_number();
+        }
+        for _ in 0..inputs {
+            let v = stack.pop_vector();
+            if v.vec().size() != size {
+                panic!("Faust inputs must have same length!");
+            }
+            ins.push(v);
+        }
+        ins.reverse();
+
+        let outs: Vec<SharedVector> = self.ovs.iter_mut().map(|slot| output(slot, size)).collect();
+        {
+            let ins = ins.iter().map(|v| v.vec()).collect::<Vec<_>>();
+            let ins: Vec<&[TNT]> = ins.iter().map(|v| v.data().as_slice()).collect();
+            let mut outs = outs.iter().map(|v| v.vec_mut()).collect::<Vec<_>>();
+            let mut outs: Vec<&mut [TNT]> = outs.iter_mut().map(|v| v.mut_data()).collect();
+
+            if controls.iter().all(|c| c.is_number()) {
+                for (p, c) in self.params.iter().zip(controls.iter()) {
+                    if let Control::N(x) = c {
+                        self.dsp.set_param(p.index, *x);
+                    }
+                }
+                self.dsp.compute(size as i32, &ins, &mut outs);
+            } else {
+                // one sample at a time for modulated controls
+                let (ni, no) = (ins.len(), outs.len());
+                for i in 0..size {
+                    for (p, c) in self.params.iter().zip(controls.iter()) {
+                        self.dsp.set_param(p.index, c.at(i));
+                    }
+                    let mut one_in: [&[TNT]; MAX_CHANNELS] = Default::default();
+                    let mut one_out: [&mut [TNT]; MAX_CHANNELS] = Default::default();
+                    for (o, s) in one_in.iter_mut().zip(ins.iter()) {
+                        *o = &s[i..i + 1];
+                    }
+                    for (o, s) in one_out.iter_mut().zip(outs.iter_mut()) {
+                        *o = &mut s[i..i + 1];
+                    }
+                    self.dsp.compute(1, &one_in[..ni], &mut one_out[..no]);
+                }
+            }
+        }
+        controls.clear();
+        for o in outs {
+            stack.push_vector(o);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::wav;
+    use crate::vector::Vector;
+    use crate::vector::resample::resample;
+    use crate::runtime::{YjrEnviroment, YjrStack, SharedVector};
+    use crate::words::testing;
+    use crate::faust::auto::*;
+    use crate::faust::words::soloop;
+    use crate::faust::words::generic::FaustWord;
+
+    fn render(script: &str, blocks: usize) -> Vec<TNT> {
+        testing::render(48000, script, blocks)
+    }
+
+    #[test]
+    fn effects() {
+        assert_eq!(FaustWord::<OsOsc::dsp>::effect(), "n n -- v | n v -- v");
+        assert_eq!(FaustWord::<NoNoise::dsp>::effect(), "n -- v");
+        assert_eq!(FaustWord::<OsOscp::dsp>::effect(), "v v -- v");
+        assert_eq!(FaustWord::<FiLowpass::dsp>::effect(), "v n -- v | v v -- v");
+        assert_eq!(FaustWord::<soloop::dsp>::effect(), "n s -- v");
+    }
+
+    #[test]
+    fn vector_frequency() {
+        // constant vector frequency is the same as the number
+        let a = render("64 440 dsp.os.osc", 4);
+        let b = render("64 440 64 ones~ * dsp.os.osc", 4);
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert!((a - b).abs() < 1.0e-4);
+        }
+
+        // a frequency step keeps the phase
+        let env = YjrEnviroment::new(48000);
+        let mut w = FaustWord::<OsOsc::dsp>::new(&env);
+        let mut stack = YjrStack::new();
+        stack.push_number(4.0);
+        stack.push_vector( SharedVector::new(Vector::new(vec![0.0, 0.0, 12000.0, 12000.0])) );
+        w.run(&mut stack);
+        let y = stack.pop_vector().vec().data().clone();
+        assert!(y[0].abs() < 1.0e-6 && y[1].abs() < 1.0e-6);
+        assert!((y[2] - 1.0).abs() < 1.0e-4);
+        assert!(y[3].abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn inputs() {
+        // the inputs of os.oscp are frequency and phase vectors
+        let env = YjrEnviroment::new(48000);
+        let mut w = FaustWord::<OsOscp::dsp>::new(&env);
+        let mut stack = YjrStack::new();
+        stack.push_vector( SharedVector::new(Vector::new(vec![12000.0; 4])) );
+        stack.push_vector( SharedVector::new(Vector::new(vec![0.0, 0.0, 0.0, std::f32::consts::PI])) );
+        w.run(&mut stack);
+        assert_eq!(stack.size(), 1);
+        let y = stack.pop_vector().vec().data().clone();
+        let expected = [1.0, 0.0, -1.0, 0.0];
+        for (y, e) in y.iter().zip(expected.iter()) {
+            assert!((y - e).abs() < 1.0e-4);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Faust inputs must have same length!")]
+    fn input_length() {
+        let env = YjrEnviroment::new(48000);
+        let mut w = FaustWord::<OsOscp::dsp>::new(&env);
+        let mut stack = YjrStack::new();
+        stack.push_vector( SharedVector::new(Vector::new(vec![100.0; 4])) );
+        stack.push_vector( SharedVector::new(Vector::new(vec![0.0; 8])) );
+        w.run(&mut stack);
+    }
+
+    #[test]
+    fn soundfiles() {
+        // a sound at half the rate loops every 6 samples
+        let path = std::env::temp_dir().join("yjriver-soundfile~loop.wav");
+        wav::write(&path, &[&[0.0, 1.0, 0.0]], 24000).unwrap();
+        let mut env = YjrEnviroment::new(48000);
+        let mut rt = env.build(&format!("8 ${} dsp.so.loop", path.display()));
+
+        // loaded at build, not while running
+        std::fs::remove_file(&path).unwrap();
+        let mut y = Vec::new();
+        for _ in 0..3 {
+            rt.run();
+            y.extend_from_slice( rt.stack.pop().as_vector().vec().data() );
+        }
+        let expected = resample(&[0.0, 1.0, 0.0], 24000, 48000);
+        for (i, v) in y.iter().enumerate() {
+            assert!((v - expected[i % 6]).abs() < 1.0e-6);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't load soundfile")]
+    fn missing_soundfile() {
+        let mut env = YjrEnviroment::new(48000);
+        env.build("8 $/nonexistent/yjriver_loop.wav dsp.so.loop");
+    }
+
+    #[test]
+    #[should_panic(expected = "needs a constant path")]
+    fn soundfile_not_constant() {
+        let mut env = YjrEnviroment::new(48000);
+        env.build("$/tmp/a.wav $sound ! 8 $sound @ dsp.so.loop");
+    }
+
+    #[test]
+    fn lowpass() {
+        // gain of the last block of a tone through the effect
+        let rms = |x: &[TNT]| (x.iter().map(|v| v * v).sum::<TNT>() / x.len() as TNT).sqrt();
+        let gain = |freq: TNT| {
+            let y = render(&format!("960 {} dsp.os.osc 1000 dsp.fi.lowpass", freq), 10);
+            rms(&y[960 * 9..]) / std::f32::consts::FRAC_1_SQRT_2
+        };
+        assert!(gain(100.0) > 0.99);
+        assert!((gain(1000.0) - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01);
+        assert!(gain(4000.0) < 0.07);
+
+        // modulated cutoff
+        let y = render("64 dsp.no.noise 1000 64 ones~ * dsp.fi.lowpass", 4);
+        assert!(y.iter().all(|v| v.is_finite()));
+    }
+}
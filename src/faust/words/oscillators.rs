@@ -1,41 +1,79 @@
-use crate::TNT;
-use crate::vector::Vector;
-use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, SharedVector};
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, SharedVector};
+use crate::words::{output, Control};
 
-use crate::faust::faust_help::{FaustDsp, ParamIndex};
+use crate::faust::faust_help::FaustDsp;
 use crate::faust::auto::*;
 
-pub struct OscWord {
+// length of the block below `params` controls
+fn block_size(stack: &YjrStack, params: usize) -> usize {
+    let n = stack.size();
+    if n <= params {
+        panic!("Oscillator needs a length and {} parameters", params);
+    }
+    match &stack.items()[n - params - 1] {
+        YjrItem::N(c) if c.fract() == 0.0 && *c >= 0.0 => *c as usize,
+        _ => panic!("Oscillator length must be a integer!"),
+    }
+}
+
+// n freq phase dsp.os.oscp -> v
+//
+// Sine with phase modulation in radians, freq and phase are numbers or
+// per-sample vectors, unlike the vector inputs of a generic word.
+pub struct PmOscWord {
     ov: Option<SharedVector>,
-    dsp: OsOsc::dsp,
+    dsp: OsOscp::dsp,
 }
-impl OscWord {
+impl PmOscWord {
     pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
-        let mut dsp = OsOsc::dsp::new();
+        let mut dsp = OsOscp::dsp::new();
         dsp.init( env.query("SampleRate").0 );
-        Box::new( OscWord{
+        Box::new( PmOscWord{
             ov: None,
-            dsp: dsp
+            dsp,
         })
     }
 }
 
-impl NativeWord for OscWord {
+impl NativeWord for PmOscWord {
     fn run(&mut self, stack: &mut YjrStack) {
-        let freq = stack.pop_number();
-        self.dsp.set_param( ParamIndex(0), freq);
-        let count = stack.pop_number() as usize;
-        if self.ov == None {
-            self.ov = Some( SharedVector::new( Vector::<TNT>::zeros(count) ) );
-        }
-        if let Some(v) = &self.ov {
-            let mut d = v.vec_mut();
-            let mut output = vec![ d.mut_data() ];
-            self.dsp.compute(count as i32, &vec![], &mut output);
+        let count = block_size(stack, 2);
+        let phase = Control::pop(stack, count).values(count);
+        let freq = Control::pop(stack, count).values(count);
+        stack.pop_number();
 
-            stack.push_vector(v.clone());
+        let out = output(&mut self.ov, count);
+        {
+            let mut d = out.vec_mut();
+            let mut output = vec![ d.mut_data() ];
+            self.dsp.compute(count as i32, &[&freq, &phase], &mut output);
         }
-        return;
+        stack.push_vector(out);
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::words::testing;
+
+    fn render(script: &str, blocks: usize) -> Vec<TNT> {
+        testing::render(48000, script, blocks)
+    }
+
+    #[test]
+    fn phase_modulation() {
+        let y = render("64 1000 0.5 dsp.os.oscp", 3);
+        for (i, v) in y.iter().enumerate() {
+            let t = (i + 1) as f64 / 48000.0;
+            let expected = (2.0 * std::f64::consts::PI * 1000.0 * t + 0.5).sin();
+            assert!((*v as f64 - expected).abs() < 1.0e-3);
+        }
+
+        // 2-operator FM, modulator at 220 Hz with index 3
+        let y = render("64 440 0 3 64 220 dsp.os.osc * + dsp.os.oscp", 8);
+        assert!(y.iter().all(|v| v.is_finite() && v.abs() <= 1.0));
+        let z = render("64 440 0 dsp.os.oscp", 8);
+        assert!(y.iter().zip(z.iter()).any(|(a, b)| (a - b).abs() > 0.5));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, NamedParams};
+
+use crate::faust::ui::path_matches;
+
+// $name value param! ->, $name null param! ->
+//
+// Sets every Faust control of the runtime matching the name, a full path
+// like `os.osc/freq` or the last labels, and `os.osc.2/freq` for only the
+// second instance. Values are clamped by the words to the declared range,
+// `null` clears them and the stack is used again.
+pub struct ParamWord {
+    named: NamedParams,
+}
+impl ParamWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( ParamWord {
+            named: env.params(),
+        })
+    }
+}
+
+impl NativeWord for ParamWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let value = match stack.pop() {
+            YjrItem::N(v) => Some(v),
+            YjrItem::S(s) if s.is_empty() => None,
+            _ => panic!("param! needs a number or null!"),
+        };
+        let name = stack.pop_string();
+        let name = name.trim_start_matches(['$', '%']);
+
+        let mut named = self.named.borrow_mut();
+        let mut found = false;
+        for (path, v) in named.iter_mut() {
+            if path_matches(path, name) {
+                *v = value;
+                found = true;
+            }
+        }
+        if !found {
+            panic!("Can't find Faust parameter '{}'", name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::runtime::YjrEnviroment;
+    use crate::words::testing;
+
+    fn render(script: &str) -> Vec<TNT> {
+        testing::render(48000, script, 1)
+    }
+
+    #[test]
+    fn by_name() {
+        // the named value wins over the stack
+        let a = render("64 1000 dsp.os.osc");
+        let b = render("$freq 1000 param! 64 220 dsp.os.osc");
+        let c = render("$os.osc/freq 1000 param! 64 220 dsp.os.osc");
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+
+        // clamped to the range of the slider
+        let a = render("64 11000 dsp.os.osc");
+        let b = render("$freq 20000 param! 64 220 dsp.os.osc");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn instances() {
+        // two oscillators side by side, each can be set alone
+        let run = |script: &str| {
+            let mut env = YjrEnviroment::new(48000);
+            let mut rt = env.build(script);
+            rt.run();
+            let b = rt.stack.pop().as_vector().vec().data().clone();
+            let a = rt.stack.pop().as_vector().vec().data().clone();
+            (a, b)
+        };
+        let low = render("64 220 dsp.os.osc");
+        let high = render("64 1000 dsp.os.osc");
+
+        let (a, b) = run("$os.osc.2/freq 1000 param! 64 220 dsp.os.osc 64 220 dsp.os.osc");
+        assert_eq!((&a, &b), (&low, &high));
+        let (a, b) = run("$os.osc.1/freq 1000 param! 64 220 dsp.os.osc 64 220 dsp.os.osc");
+        assert_eq!((&a, &b), (&high, &low));
+
+        // without the suffix every instance is set
+        let (a, b) = run("$freq 1000 param! 64 220 dsp.os.osc 64 220 dsp.os.osc");
+        assert_eq!((&a, &b), (&high, &high));
+
+        // null gives the control back to the stack
+        let (a, b) = run("$freq 1000 param! $os.osc.1/freq null param! 64 220 dsp.os.osc 64 220 dsp.os.osc");
+        assert_eq!((&a, &b), (&low, &high));
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't find Faust parameter 'gain'")]
+    fn unknown() {
+        render("64 220 dsp.os.osc $gain 0.5 param!");
+    }
+}
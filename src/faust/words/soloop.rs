@@ -0,0 +1,124 @@
+// Hand written dsp of dsp/so.loop.dsp, laid out as the generated ones. The
+// Faust rust backend makes no code for `set_soundfile` and `SoundfileIndex`
+// of faust_help.rs, so soundfile dsps can't be generated into auto yet.
+
+#![allow(unused_parens)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+#![allow(non_upper_case_globals)]
+#![allow(clippy::all)]
+
+use crate::faust::faust_help::*;
+
+
+pub struct dsp {
+	fSampleRate: i32,
+	fSoundfile0: Option<Rc<Soundfile>>,
+	iRec0: [i32;2],
+}
+
+impl FaustDsp for dsp {
+	type T = F32;
+
+	fn new() -> dsp {
+		dsp {
+			fSampleRate: 0,
+			fSoundfile0: None,
+			iRec0: [0;2],
+		}
+	}
+	fn metadata(&self, m: &mut dyn Meta) {
+		m.declare("basics.lib/name", "Faust Basic Element Library");
+		m.declare("basics.lib/version", "0.8");
+		m.declare("filename", "so.loop.dsp");
+		m.declare("name", "so.loop");
+		m.declare("soundfiles.lib/name", "Faust Soundfile Library");
+		m.declare("soundfiles.lib/version", "0.7");
+	}
+
+	fn get_sample_rate(&self) -> i32 {
+		return self.fSampleRate;
+	}
+	fn get_num_inputs(&self) -> i32 {
+		return 0;
+	}
+	fn get_num_outputs(&self) -> i32 {
+		return 1;
+	}
+
+	fn class_init(sample_rate: i32) {
+	}
+	fn instance_reset_params(&mut self) {
+	}
+	fn instance_clear(&mut self) {
+		for l0 in 0..2 {
+			self.iRec0[(l0) as usize] = 0;
+		}
+	}
+	fn instance_constants(&mut self, sample_rate: i32) {
+		self.fSampleRate = sample_rate;
+	}
+	fn instance_init(&mut self, sample_rate: i32) {
+		self.instance_constants(sample_rate);
+		self.instance_reset_params();
+		self.instance_clear();
+	}
+	fn init(&mut self, sample_rate: i32) {
+		dsp::class_init(sample_rate);
+		self.instance_init(sample_rate);
+	}
+
+	fn build_user_interface(&self, ui_interface: &mut dyn UI<Self::T>) {
+		Self::build_user_interface_static(ui_interface);
+	}
+
+	fn build_user_interface_static(ui_interface: &mut dyn UI<Self::T>) {
+		ui_interface.open_vertical_box("so.loop");
+		ui_interface.add_soundfile("sound", "{'sound.wav'}", SoundfileIndex(0));
+		ui_interface.close_box();
+	}
+
+	fn get_param(&self, param: ParamIndex) -> Option<Self::T> {
+		match param.0 {
+			_ => None,
+		}
+	}
+
+	fn set_param(&mut self, param: ParamIndex, value: Self::T) {
+		match param.0 {
+			_ => {}
+		}
+	}
+
+	fn set_soundfile(&mut self, sf: SoundfileIndex, soundfile: Rc<Soundfile>) {
+		match sf.0 {
+			0 => { self.fSoundfile0 = Some(soundfile) }
+			_ => {}
+		}
+	}
+
+	fn compute(&mut self, count: i32, inputs: &[&[Self::T]], outputs: &mut[&mut[Self::T]]) {
+		let (outputs0) = if let [outputs0, ..] = outputs {
+			let outputs0 = outputs0[..count as usize].iter_mut();
+			(outputs0)
+		} else {
+			panic!("wrong number of outputs");
+		};
+		let fSoundfile0Ca = self.fSoundfile0.clone().expect("soundfile is not set");
+		let mut iSlow0: i32 = fSoundfile0Ca.fLength[0];
+		let mut iSlow1: i32 = fSoundfile0Ca.fOffset[0];
+		let fSoundfile0Ca_bu_ch0 = &fSoundfile0Ca.fBuffers[0];
+		let zipped_iterators = outputs0;
+		for output0 in zipped_iterators {
+			let mut iTemp0: i32 = self.iRec0[1];
+			*output0 = fSoundfile0Ca_bu_ch0[(iSlow1 + std::cmp::max(0, std::cmp::min(iTemp0, iSlow0 + -1))) as usize];
+			self.iRec0[0] = (iTemp0 + 1) % iSlow0;
+			self.iRec0[1] = self.iRec0[0];
+		}
+	}
+
+}
+
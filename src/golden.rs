@@ -0,0 +1,281 @@
+//! Golden-audio regression tests.
+//!
+//! Every case renders a script for `BLOCKS` runs, collecting the top
+//! of stack of each run, and compares the signal with the reference in
+//! `golden/<name>.txt`. Set `YJR_GOLDEN_UPDATE=1` (or `true`) to
+//! regenerate the references after an intended change of sound.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::TNT;
+use crate::runtime::{YjrEnviroment, YjrItem};
+
+pub const SAMPLE_RATE: i32 = 44100;
+pub const SEED: i32 = 1979;
+pub const BLOCKS: usize = 4;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Tolerance {
+    pub max_abs: TNT,
+    pub min_snr: f64,       // in dB, not checked for silent references
+}
+
+pub const TOLERANCE: Tolerance = Tolerance {
+    max_abs: 1.0e-4,
+    min_snr: 80.0,
+};
+
+pub fn render(script: &str, blocks: usize) -> Vec<TNT> {
+    let mut env = YjrEnviroment::new(SAMPLE_RATE);
+    env.config("Seed", (SEED, 0.0, false));
+    let mut rt = env.build(script);
+
+    let mut out = Vec::new();
+    for _ in 0..blocks {
+        rt.run();
+        match rt.stack.pop() {
+            YjrItem::V(v) => out.extend_from_slice( v.vec().data() ),
+            YjrItem::M(m) => {
+                // interleaved frames
+                let frames = if m.is_empty() { 0 } else { m[0].vec().size() };
+                for i in 0..frames {
+                    for c in &m {
+                        out.push( c.vec()[i] );
+                    }
+                }
+            },
+            YjrItem::N(n) => out.push(n),
+            YjrItem::S(s) => panic!("Rendering leaves string '{}' on stack", s),
+        }
+        rt.stack.clear();
+    }
+    out
+}
+
+/// Compares a rendered signal with its reference, the error is a
+/// readable summary of the difference.
+pub fn compare(got: &[TNT], expected: &[TNT], tol: &Tolerance) -> Result<(), String> {
+    if got.len() != expected.len() {
+        return Err( format!("length is {}, expected {}", got.len(), expected.len()) );
+    }
+
+    let mut max_abs: TNT = 0.0;
+    let mut max_at = 0;
+    let mut over = Vec::new();
+    let mut signal = 0.0f64;
+    let mut noise = 0.0f64;
+    for i in 0..got.len() {
+        let e = if got[i].is_nan() || expected[i].is_nan() {
+            if got[i].is_nan() && expected[i].is_nan() { 0.0 } else { TNT::INFINITY }
+        } else {
+            (got[i] - expected[i]).abs()
+        };
+        if e > max_abs {
+            max_abs = e;
+            max_at = i;
+        }
+        if e > tol.max_abs {
+            over.push(i);
+        }
+        if expected[i].is_finite() {
+            signal += (expected[i] as f64).powi(2);
+        }
+        noise += (e as f64).powi(2);
+    }
+    let snr = 10.0 * (signal / noise).log10();
+
+    if max_abs <= tol.max_abs && (signal == 0.0 || noise == 0.0 || snr >= tol.min_snr) {
+        return Ok(());
+    }
+
+    let mut msg = format!("max abs error {:e} at sample {} (got {}, expected {}), SNR {:.1} dB, {} of {} samples over {:e}",
+                          max_abs, max_at, got[max_at], expected[max_at], snr, over.len(), got.len(), tol.max_abs);
+    for &i in over.iter().take(8) {
+        msg.push_str( &format!("\n  [{}] got {}, expected {}", i, got[i], expected[i]) );
+    }
+    if over.len() > 8 {
+        msg.push_str("\n  ...");
+    }
+    Err(msg)
+}
+
+// only an explicit 1 or true, other values keep the references
+fn updating() -> bool {
+    matches!(std::env::var("YJR_GOLDEN_UPDATE").as_deref(), Ok("1") | Ok("true"))
+}
+
+fn reference(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("golden").join(format!("{}.txt", name))
+}
+
+/// Renders the script and checks it against reference `name`.
+pub fn check(name: &str, script: &str) {
+    let got = render(script, BLOCKS);
+    let path = reference(name);
+
+    if updating() {
+        let mut txt = format!("; {}\n", script.split_whitespace().collect::<Vec<_>>().join(" "));
+        for x in &got {
+            txt.push_str( &format!("{}\n", x) );
+        }
+        fs::create_dir_all( path.parent().unwrap() ).unwrap();
+        fs::write(&path, txt).unwrap();
+        return;
+    }
+
+    let txt = match fs::read_to_string(&path) {
+        Ok(txt) => txt,
+        Err(_) => panic!("Missing golden reference {:?}, run with YJR_GOLDEN_UPDATE=1 to create it", path),
+    };
+    let expected: Vec<TNT> = txt.lines()
+        .filter(|l| !l.starts_with(';'))
+        .map(|l| l.trim().parse::<TNT>().unwrap())
+        .collect();
+
+    if let Err(msg) = compare(&got, &expected, &TOLERANCE) {
+        panic!("Golden test '{}' failed: {}", name, msg);
+    }
+}
+
+macro_rules! golden_test {
+    ($name:ident, $script:expr) => {
+        #[test]
+        fn $name() {
+            crate::golden::check(stringify!($name), $script);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::golden::{compare, TOLERANCE};
+
+    #[test]
+    fn compare_signals() {
+        let a = vec![0.0, 0.5, 1.0, -0.5];
+        assert!(compare(&a, &a, &TOLERANCE).is_ok());
+        assert!(compare(&a, &[0.0, 0.5, 1.00001, -0.5], &TOLERANCE).is_ok());
+
+        let e = compare(&a, &[0.0, 0.5, 1.0], &TOLERANCE).unwrap_err();
+        assert_eq!(e, "length is 4, expected 3");
+
+        let e = compare(&a, &[0.0, 0.5, 0.5, -0.5], &TOLERANCE).unwrap_err();
+        assert!(e.starts_with("max abs error 5e-1 at sample 2 (got 1, expected 0.5)"));
+        assert!(e.contains("1 of 4 samples"));
+
+        let e = compare(&[TNT::NAN], &[0.0], &TOLERANCE).unwrap_err();
+        assert!(e.starts_with("max abs error inf"));
+    }
+
+    // builtin words
+    golden_test!(zeros, "64 zeros~");
+    golden_test!(ones, "64 ones~");
+    golden_test!(drop, "64 ones~ 64 zeros~ drop");
+    golden_test!(dup, "64 220 dsp.os.osc dup *");
+    golden_test!(dup2, "64 ones~ 64 220 dsp.os.osc dup2 drop * +");
+    golden_test!(swap, "64 220 dsp.os.osc 64 zeros~ swap");
+    golden_test!(rot, "64 220 dsp.os.osc 64 zeros~ 64 ones~ rot");
+
+    // arithmetic, vector & number and vector & vector
+    golden_test!(add, "0.5 64 220 dsp.os.osc + 64 330 dsp.os.osc +");
+    golden_test!(sub, "0.5 64 220 dsp.os.osc - 64 330 dsp.os.osc -");
+    golden_test!(mul, "0.5 64 220 dsp.os.osc * 64 330 dsp.os.osc *");
+    golden_test!(div, "4 64 220 dsp.os.osc / 2 64 ones~ * swap /");
+    golden_test!(rem, "0.3 64 220 dsp.os.osc % 0.7 64 ones~ * %");
+
+    // unary
+    golden_test!(abs, "64 220 dsp.os.osc abs");
+    golden_test!(acos, "64 220 dsp.os.osc acos");
+    golden_test!(acosh, "1 64 220 dsp.os.osc abs + acosh");
+    golden_test!(asin, "64 220 dsp.os.osc asin");
+    golden_test!(asinh, "64 220 dsp.os.osc asinh");
+    golden_test!(atan, "64 220 dsp.os.osc atan");
+    golden_test!(atanh, "0.9 64 220 dsp.os.osc * atanh");
+    golden_test!(cbrt, "64 220 dsp.os.osc cbrt");
+    golden_test!(ceil, "4 64 220 dsp.os.osc * ceil");
+    golden_test!(cos, "3 64 220 dsp.os.osc * cos");
+    golden_test!(cosh, "64 220 dsp.os.osc cosh");
+    golden_test!(exp, "64 220 dsp.os.osc exp");
+    golden_test!(exp2, "64 220 dsp.os.osc exp2");
+    golden_test!(floor, "4 64 220 dsp.os.osc * floor");
+    golden_test!(fract, "4 64 220 dsp.os.osc * fract");
+    golden_test!(ln, "1.5 64 220 dsp.os.osc + ln");
+    golden_test!(log10, "1.5 64 220 dsp.os.osc + log10");
+    golden_test!(recip, "1.5 64 220 dsp.os.osc + recip");
+    golden_test!(round, "4 64 220 dsp.os.osc * round");
+    golden_test!(sin, "3 64 220 dsp.os.osc * sin");
+    golden_test!(sinh, "64 220 dsp.os.osc sinh");
+    golden_test!(sqrt, "64 220 dsp.os.osc abs sqrt");
+    golden_test!(tan, "64 220 dsp.os.osc tan");
+    golden_test!(tanh, "3 64 220 dsp.os.osc * tanh");
+    golden_test!(trunc, "4 64 220 dsp.os.osc * trunc");
+
+    // binary functions
+    golden_test!(atan2, "0.5 64 220 dsp.os.osc atan2 64 330 dsp.os.osc atan2");
+    golden_test!(hypot, "0.5 64 220 dsp.os.osc hypot 64 330 dsp.os.osc hypot");
+    golden_test!(log, "2 1.5 64 220 dsp.os.osc + log");
+    golden_test!(min, "0.5 64 220 dsp.os.osc min 64 330 dsp.os.osc min");
+    golden_test!(max, "0.5 64 220 dsp.os.osc max 64 330 dsp.os.osc max");
+    golden_test!(powf, "2 64 220 dsp.os.osc abs powf");
+    golden_test!(clamp, "-0.5 0.5 64 220 dsp.os.osc clamp");
+
+    // reductions
+    golden_test!(sum, "64 220 dsp.os.osc sum");
+    golden_test!(mean, "64 220 dsp.os.osc mean");
+    golden_test!(var, "64 220 dsp.os.osc var");
+    golden_test!(dot, "64 220 dsp.os.osc 64 330 dsp.os.osc dot");
+
+    // multichannel, frames are interleaved
+    golden_test!(channels, "64 220 dsp.os.osc 64 330 dsp.os.osc 2 channels~ 3 swap * tanh");
+    golden_test!(merge, "64 220 dsp.os.osc 64 330 dsp.os.osc merge~ 64 ones~ merge~ 1 chan~");
+
+    // spectral
+    golden_test!(fft, "64 220 dsp.os.osc 64 330 dsp.os.osc + fft polar drop");
+    golden_test!(stft, "64 220 dsp.os.osc 128 32 $hann stft~ 0.5 swap * 128 32 $hann istft~");
+
+    // filters
+    golden_test!(lpf, "64 dsp.no.noise 2000 0.7071 lpf~");
+    golden_test!(peak, "64 dsp.no.noise 1000 500 64 220 dsp.os.osc * + 2 6 peak~");
+
+    // convolution, direct and partitioned
+    golden_test!(conv_fir, "64 220 dsp.os.osc 0.1 32 ones~ * conv~");
+    golden_test!(conv_fft, "64 220 dsp.os.osc 0.01 300 dsp.no.noise * conv~");
+
+    // delays
+    golden_test!(delay, "64 220 dsp.os.osc dup 0.0003 delay~ +");
+    golden_test!(reverb, "512 dsp.no.noise 0.8 0.3 0.5 reverb~");
+
+    // envelopes
+    golden_test!(adsr, "64 220 dsp.os.osc 64 ones~ 0.001 0.002 0.6 0.1 adsr~ *");
+    golden_test!(adsre, "64 ones~ 0.002 0.001 0.3 0.1 adsre~");
+
+    // band-limited oscillators
+    golden_test!(saw, "64 3000 saw~");
+    golden_test!(pulse, "64 1500 0.25 pulse~");
+    golden_test!(tri, "64 5000 tri~");
+    golden_test!(sync, "64 1234 500 sync~");
+
+    // wavetables
+    golden_test!(wavetable, "64 256 1000 saw~ 256 300 tri~ 2 channels~ 3000 64 ones~ 0.01 0.01 0.5 0.1 adsr~ wavetable~");
+
+    // seeded noises
+    golden_test!(pink, "64 pink~ 64 brown~ +");
+    golden_test!(velvet, "64 2000 velvet~ 0.1 64 gauss~ * +");
+
+    // state across blocks
+    golden_test!(prev, "0.5 64 dsp.no.noise dup prev~ + *");
+    golden_test!(accum, "64 dsp.no.noise last 0.1 accum sin 64 220 dsp.os.osc * +");
+
+    // per-sample words
+    golden_test!(sample_onepole, "#def~ onepole z1 0.95 * swap 0.05 * + #end 64 dsp.no.noise onepole");
+    golden_test!(sample_fm, "#def~ selffm z1 0.8 * + sin #end 0.1 64 ones~ * selffm");
+
+    // faust wrappers
+    golden_test!(dsp_os_osc, "64 440 dsp.os.osc");
+    golden_test!(dsp_no_noise, "64 dsp.no.noise");
+    golden_test!(dsp_os_oscp, "64 440 0 3 64 220 dsp.os.osc * + dsp.os.oscp");
+    golden_test!(dsp_fi_lowpass, "64 dsp.no.noise 2000 dsp.fi.lowpass");
+    golden_test!(dsp_os_osc_fm, "64 440 100 64 5 dsp.os.osc * + dsp.os.osc");
+}
@@ -1,3 +1,6 @@
+// native words are built as boxed trait objects by `new`
+#![allow(clippy::new_ret_no_self)]
+
 // third party library.
 extern crate num as libnum;
 
@@ -8,9 +11,20 @@ pub type TNT = f32;
 #[macro_use]
 pub mod macros;
 
+#[cfg(test)]
+#[macro_use]
+mod golden;
+
 pub mod vector;
 pub mod runtime;
+pub mod debug;
+pub mod wav;
+#[cfg(feature = "profile")]
+pub mod profile;
 
+mod threaded;
+mod verify;
 mod base;
 mod math;
 mod faust;
+mod words;
@@ -1,5 +1,43 @@
+use crate::TNT;
 use crate::vector::Vector;
-use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, SharedVector};
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, SharedVector};
+
+type VectorOp<'a> = &'a dyn Fn(&Vector<TNT>, &Vector<TNT>) -> Vector<TNT>;
+type ScalarOp<'a> = &'a dyn Fn(&Vector<TNT>, TNT) -> Vector<TNT>;
+
+// binary words over multichannel items work on each channel, a vector
+// is applied to every channel. Returns false when none of the two top
+// items is multichannel.
+fn broadcast(stack: &mut YjrStack, vv: VectorOp, vn: ScalarOp) -> bool {
+    let items = stack.items();
+    let n = items.len();
+    if n < 2 || !matches!((&items[n-1], &items[n-2]), (YjrItem::M(_), _) | (YjrItem::V(_), YjrItem::M(_))) {
+        return false;
+    }
+
+    let a = stack.pop();
+    let b = stack.pop();
+    let c: Vec<SharedVector> = match (a, b) {
+        (YjrItem::M(a), YjrItem::M(b)) => {
+            if a.len() != b.len() {
+                panic!("Multichannel items must have same number of channels");
+            }
+            a.iter().zip(b.iter()).map(|(x, y)| SharedVector::new( vv(&x.vec(), &y.vec()) )).collect()
+        },
+        (YjrItem::M(a), YjrItem::V(b)) => {
+            a.iter().map(|x| SharedVector::new( vv(&x.vec(), &b.vec()) )).collect()
+        },
+        (YjrItem::M(a), YjrItem::N(b)) => {
+            a.iter().map(|x| SharedVector::new( vn(&x.vec(), b) )).collect()
+        },
+        (YjrItem::V(a), YjrItem::M(b)) => {
+            b.iter().map(|y| SharedVector::new( vv(&a.vec(), &y.vec()) )).collect()
+        },
+        _ => panic!("Can't broadcast multichannel item with a string"),
+    };
+    stack.push_multi(c);
+    true
+}
 
 macro_rules! math_vector_number_op {
     ($name:ident, $op:ident) => {
@@ -11,6 +49,12 @@ macro_rules! math_vector_number_op {
         }
         impl NativeWord for $name {
             fn run(&mut self, stack: &mut YjrStack) {
+                if stack.top().is_multi() {
+                    let m = stack.pop_multi();
+                    let b: Vec<TNT> = m.iter().map(|a| a.vec().$op()).collect();
+                    stack.push_vector( SharedVector::new( Vector::new(b) ) );
+                    return;
+                }
                 let a = stack.pop_vector();
                 let b = a.vec().$op();
                 stack.push_number(b);
@@ -54,6 +98,10 @@ macro_rules! math_vector_unary_op {
                     let a = stack.pop_vector();
                     let b = SharedVector::new( a.vec().$op() );
                     stack.push_vector(b);
+                } else if stack.top().is_multi() {
+                    let m = stack.pop_multi();
+                    let b = m.iter().map(|a| SharedVector::new( a.vec().$op() )).collect();
+                    stack.push_multi(b);
                 } else {
                     let a = stack.pop_number();
                     let b = a.$op();
@@ -100,6 +148,9 @@ macro_rules! math_binary_op {
         }
         impl NativeWord for $name {
             fn run(&mut self, stack: &mut YjrStack) {
+                if broadcast(stack, &|x, y| x $op y, &|x, y| x $op y) {
+                    return;
+                }
                 if stack.top().is_vector() {
                     let a = stack.pop_vector();
                     if stack.top().is_vector() {
@@ -139,6 +190,9 @@ macro_rules! math_binary_fn {
         }
         impl NativeWord for $name {
             fn run(&mut self, stack: &mut YjrStack) {
+                if broadcast(stack, &|x, y| x.$fn__(y), &|x, y| x.$fn_(y)) {
+                    return;
+                }
                 if stack.top().is_vector() {
                     let a = stack.pop_vector();
                     if stack.top().is_vector() {
@@ -178,16 +232,24 @@ impl Clamp {
 }
 impl NativeWord for Clamp {
     fn run(&mut self, stack: &mut YjrStack) {
-        let v: SharedVector = stack.pop_vector();
+        fn clamp(v: &SharedVector, a: TNT, b: TNT) -> SharedVector {
+            let mut new_data = Vec::with_capacity(v.vec().size());
+            for idx in v.vec().iter() {
+                new_data.push( idx.clamp(a, b) );
+            }
+            SharedVector::new( Vector::new(new_data) )
+        }
+
+        let v = stack.pop();
         let b = stack.pop_number();
         let a = stack.pop_number();
 
-        let mut new_data = Vec::with_capacity(v.vec().size());
-        for idx in v.vec().iter() {
-            new_data.push( idx.clamp(a, b) );
+        if v.is_multi() {
+            let m = v.as_multi().iter().map(|c| clamp(c, a, b)).collect();
+            stack.push_multi(m);
+        } else {
+            stack.push_vector( clamp(&v.as_vector(), a, b) );
         }
-
-        stack.push_vector( SharedVector::new( Vector::new(new_data) ) );
     }
 }
 
@@ -197,6 +259,7 @@ pub fn insert_native_words(env: &mut YjrEnviroment) {
     // vector with vector to vector
     // vector with number to vector
     // number with number to number
+    // multichannel with any of them to multichannel
     env.insert_native_word("+",  Add::new);
     env.insert_native_word("-",  Sub::new);
     env.insert_native_word("%",  Mod::new);
@@ -242,12 +305,54 @@ pub fn insert_native_words(env: &mut YjrEnviroment) {
 
     env.insert_native_word("clamp",  Clamp::new);
 
-    // vector reduce to number
+    // vector reduce to number, multichannel to vector of channels
     env.insert_native_word("sum",  Sum::new);
     env.insert_native_word("mean",  Mean::new);
     env.insert_native_word("var",  Var::new);
 
     // vector and vector to number
     env.insert_native_word("dot",  Dot::new);
+
+    // stack effects, the top item is the first operand
+    for w in ["+", "-", "%", "*", "/", "atan2", "hypot", "log", "min", "max", "powf"] {
+        env.insert_native_effect(w, "n n -- n | n v -- v | v v -- v | n m -- m | v m -- m | m v -- m | m m -- m");
+    }
+    for w in ["abs", "acos", "acosh", "asin", "asinh", "atan", "atanh", "cbrt", "ceil", "cos", "cosh",
+              "exp", "exp2", "floor", "fract", "ln", "log10", "recip", "round", "sin", "sinh",
+              "sqrt", "tan", "tanh", "trunc"] {
+        env.insert_native_effect(w, "n -- n | v -- v | m -- m");
+    }
+    env.insert_native_effect("clamp", "n n v -- v | n n m -- m");
+    for w in ["sum", "mean", "var"] {
+        env.insert_native_effect(w, "v -- n | m -- v");
+    }
+    env.insert_native_effect("dot", "v v -- n");
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::runtime::{YjrEnviroment, YjrItem};
+
+    #[test]
+    fn multichannel_broadcast() {
+        let mut env = YjrEnviroment::new(44100);
+        let txt = "4 ones~ 4 zeros~ 2 channels~
+                   4 ones~ swap +
+                   dup *
+                   sum";
+        let mut rt = env.build(txt);
+        rt.run();
+        assert_eq!(rt.stack.size(), 1);
+        match rt.stack.pop() {
+            YjrItem::V(v) => assert_eq!(v.vec().data(), &vec![16.0, 4.0]),
+            _ => panic!("sum of multichannel should be vector"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "word `+`: type mismatch")]
+    fn multichannel_check() {
+        let mut env = YjrEnviroment::new(44100);
+        env.build("4 ones~ 4 zeros~ 2 channels~ 0.5 +");
+    }
+}
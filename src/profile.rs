@@ -0,0 +1,180 @@
+//! Per-word profiling of runtime execution.
+//!
+//! Only built with the `profile` feature, and only recording after
+//! `YjrRuntime::start_profile`. Native words are counted per slot,
+//! user words per linked binary, i.e. per call site.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::runtime::{YjrStack, YjrItem, SrcInfo};
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct ProfileEntry {
+    pub calls:   u64,
+    pub total:   Duration,
+    pub max:     Duration,
+    pub samples: u64,       // size of vectors or channels left on top of stack
+}
+
+impl ProfileEntry {
+    fn record(&mut self, start: Instant, stack: &YjrStack) {
+        let t = start.elapsed();
+        self.calls += 1;
+        self.total += t;
+        if t > self.max {
+            self.max = t;
+        }
+        match stack.items().last() {
+            Some(YjrItem::V(v)) => self.samples += v.vec().size() as u64,
+            Some(YjrItem::M(m)) => self.samples += m.iter().map(|v| v.vec().size() as u64).sum::<u64>(),
+            _ => {},
+        }
+    }
+}
+
+pub struct Profile {
+    natives: Vec<ProfileEntry>,
+    binarys: Vec<ProfileEntry>,
+}
+
+impl Profile {
+    pub(crate) fn new(natives: usize, binarys: usize) -> Self {
+        Profile {
+            natives: vec![ProfileEntry::default(); natives],
+            binarys: vec![ProfileEntry::default(); binarys],
+        }
+    }
+
+    pub(crate) fn record_native(&mut self, n: usize, start: Instant, stack: &YjrStack) {
+        self.natives[n].record(start, stack);
+    }
+
+    pub(crate) fn record_user(&mut self, i: usize, start: Instant, stack: &YjrStack) {
+        self.binarys[i].record(start, stack);
+    }
+
+    /// Builds the report, `natives` and `users` are the source of each
+    /// native slot and the call site of each binary.
+    pub(crate) fn report(&self, natives: Vec<SrcInfo>, users: Vec<Option<SrcInfo>>) -> ProfileReport {
+        let mut rows = Vec::new();
+        for (n, info) in natives.into_iter().enumerate() {
+            rows.push( ProfileRow {
+                native: true,
+                word: info.word,
+                line: info.line,
+                entry: self.natives[n],
+            });
+        }
+        for (i, info) in users.into_iter().enumerate() {
+            if let Some(info) = info {
+                rows.push( ProfileRow {
+                    native: false,
+                    word: info.word,
+                    line: info.line,
+                    entry: self.binarys[i],
+                });
+            }
+        }
+        rows.sort_by_key(|r| std::cmp::Reverse(r.entry.total));
+        ProfileReport { rows }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProfileRow {
+    pub native: bool,       // native slot or user word
+    pub word:   String,
+    pub line:   usize,
+    pub entry:  ProfileEntry,
+}
+
+/// Rows sorted by total time, most expensive first.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProfileReport {
+    pub rows: Vec<ProfileRow>,
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>12} {:>12} {:>10} {:>12}  {:>5}  word",
+                 "total(us)", "max(us)", "calls", "samples", "line")?;
+        for r in &self.rows {
+            let word = if r.native { r.word.clone() } else { format!("`{}`", r.word) };
+            writeln!(f, "{:>12.1} {:>12.1} {:>10} {:>12}  {:>5}  {}",
+                     r.entry.total.as_secs_f64() * 1e6,
+                     r.entry.max.as_secs_f64() * 1e6,
+                     r.entry.calls,
+                     r.entry.samples,
+                     r.line,
+                     word)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::YjrEnviroment;
+
+    #[test]
+    fn profile_words() {
+        let mut env = YjrEnviroment::new(41100);
+        let txt = "#def osc dsp.os.osc sin #end
+                   64 440 osc
+                   64 zeros~ +";
+        let mut rt = env.build(txt);
+        rt.run();
+        assert!(rt.profile().is_none());
+
+        rt.start_profile();
+        for _ in 0..4 {
+            rt.run();
+        }
+        let report = rt.profile().unwrap();
+        assert_eq!(report.rows.len(), 5);
+
+        let osc = report.rows.iter().find(|r| r.word == "dsp.os.osc").unwrap();
+        assert!(osc.native);
+        assert_eq!(osc.line, 1);
+        assert_eq!(osc.entry.calls, 4);
+        assert_eq!(osc.entry.samples, 256);
+
+        let user = report.rows.iter().find(|r| r.word == "osc").unwrap();
+        assert!(!user.native);
+        assert_eq!(user.line, 2);
+        assert_eq!(user.entry.calls, 4);
+        assert!(user.entry.total >= osc.entry.total);
+
+        for i in 1..report.rows.len() {
+            assert!(report.rows[i-1].entry.total >= report.rows[i].entry.total);
+        }
+        assert!(format!("{}", report).contains("`osc`"));
+
+        rt.stop_profile();
+        assert!(rt.profile().is_none());
+    }
+
+    #[test]
+    fn profile_sample_words() {
+        let mut env = YjrEnviroment::new(41100);
+        let txt = "#def~ onepole z1 0.9 * swap 0.1 * + #end
+                   64 ones~ onepole";
+        let mut rt = env.build(txt);
+        rt.start_profile();
+        for _ in 0..3 {
+            rt.run();
+        }
+        let report = rt.profile().unwrap();
+        let onepole = report.rows.iter().find(|r| r.word == "onepole").unwrap();
+        assert!(!onepole.native);
+        assert_eq!(onepole.line, 2);
+        assert_eq!(onepole.entry.calls, 3);
+        assert_eq!(onepole.entry.samples, 192);
+
+        // natives of the body run once a sample
+        let mul = report.rows.iter().find(|r| r.word == "*").unwrap();
+        assert_eq!(mul.entry.calls, 192);
+        assert!(onepole.entry.total >= mul.entry.total);
+    }
+}
@@ -1,5 +1,8 @@
-use std::cell::{RefCell, RefMut, Ref};
+use std::cell::{Cell, RefCell, RefMut, Ref};
 use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::rc::Rc;
 
 use crate::vector::Vector;
@@ -7,6 +10,14 @@ use crate::TNT;
 use crate::base;
 use crate::math;
 use crate::faust;
+use crate::words;
+use crate::words::{output, Control};
+use crate::wav;
+use crate::verify::{StackEffect, TypeStack, Ty};
+use crate::debug::{Trace, TraceHook, YjrDebugger};
+use crate::threaded::{self, Thread};
+#[cfg(feature = "profile")]
+use crate::profile::{Profile, ProfileReport};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct SharedVector(Rc<RefCell<Vector<TNT>>>);
@@ -27,15 +38,16 @@ impl SharedVector {
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum YjrItem {
-    S( String ),        // string, used as flag or hash key
+    S( Rc<str> ),       // string, used as flag or hash key, shared by copies
     N( TNT ),           // number
     V( SharedVector ),  // vector
+    M( Vec<SharedVector> ),     // multichannel, channels have same length
 }
 
 
 impl YjrItem {
     pub fn new() -> Self {
-        YjrItem::S("".to_string())
+        YjrItem::S("".into())
     }
 
     pub fn is_string(&self) -> bool {
@@ -56,21 +68,18 @@ impl YjrItem {
             _ => false,
         }
     }
+    pub fn is_multi(&self) -> bool {
+        matches!(self, YjrItem::M(_))
+    }
     pub fn is_none(&self) -> bool {
         match self {
-            YjrItem::S(v) => {
-                if v == "" {
-                    true
-                } else {
-                    false
-                }
-            },
+            YjrItem::S(v) => v.is_empty(),
             _ => false,
         }
     }
 
     // consuming
-    pub fn as_string(self) -> String {
+    pub fn as_string(self) -> Rc<str> {
         match self {
             YjrItem::S(s) => s,
             _ => panic!("Item is not string")
@@ -90,6 +99,13 @@ impl YjrItem {
             _ => panic!("Item is not vector")
         }
     }
+
+    pub fn as_multi(self) -> Vec<SharedVector> {
+        match self {
+            YjrItem::M(m) => m,
+            _ => panic!("Item is not multichannel")
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -112,6 +128,11 @@ impl YjrStack {
         self.data.clear();
     }
 
+    // all items, top is the last one
+    pub fn items(&self) -> &[YjrItem] {
+        &self.data
+    }
+
     pub fn top(&self) -> &YjrItem {
         self.data.last().unwrap()
     }
@@ -159,11 +180,16 @@ impl YjrStack {
         self.data.push( item );
     }
 
-    fn push_string<T: ToString>(&mut self, s: T) {
-        let item = YjrItem::S(s.to_string());
+    pub fn push_string<T: ToString>(&mut self, s: T) {
+        let item = YjrItem::S(s.to_string().into());
         self.data.push( item );
     }
 
+    // copies of items built once, strings are shared
+    pub(crate) fn push_items(&mut self, items: &[YjrItem]) {
+        self.data.extend_from_slice(items);
+    }
+
     /*
     fn push_string_list(&mut self, sl: Vec<String>) {
         let lsize = sl.len();
@@ -192,6 +218,17 @@ impl YjrStack {
         self.data.push(item);
     }
 
+    pub fn push_multi(&mut self, m: Vec<SharedVector>) {
+        if m.len() > 1 {
+            let frames = m[0].vec().size();
+            if m.iter().any(|v| v.vec().size() != frames) {
+                panic!("Channels of multichannel item must have same length");
+            }
+        }
+        let item = YjrItem::M(m);
+        self.data.push(item);
+    }
+
     pub fn push_vector_list(&mut self, sl: Vec<SharedVector>) {
         let lsize = sl.len();
         for s in sl {
@@ -200,7 +237,7 @@ impl YjrStack {
         self.push_number(lsize as TNT);
     }
 
-    pub fn pop_string(&mut self) -> String {
+    pub fn pop_string(&mut self) -> Rc<str> {
         self.data.pop().unwrap().as_string()
     }
 
@@ -212,11 +249,15 @@ impl YjrStack {
         self.data.pop().unwrap().as_vector()
     }
 
+    pub fn pop_multi(&mut self) -> Vec<SharedVector> {
+        self.data.pop().unwrap().as_multi()
+    }
+
     pub fn pop_string_list(&mut self) -> Vec<String> {
         let lsize = self.pop_number() as usize;
         let mut ret = vec![String::new(); lsize];
         for i in 0..lsize {
-            ret[lsize - i - 1] = self.pop_string();
+            ret[lsize - i - 1] = self.pop_string().to_string();
         }
         return ret;
     }
@@ -263,14 +304,26 @@ impl YjrHash {
             panic!("Hash is out of range!");
         }
     }
-    pub fn find(&self, _name: &str) -> bool {
-        todo!()
+    pub fn target(&self) -> usize {
+        self.target
+    }
+    pub fn find(&self, name: &str) -> bool {
+        self.maps[self.target].contains_key(name)
+    }
+    pub fn get(&self, name: &str) -> YjrItem {
+        match self.maps[self.target].get(name) {
+            Some(item) => item.clone(),
+            None => YjrItem::new(),
+        }
     }
-    pub fn get(&self, _name: &str) -> YjrItem {
-        todo!()
+    pub fn set(&mut self, name: &str, item: YjrItem) {
+        self.maps[self.target].insert(name.to_string(), item);
     }
-    pub fn set(&self, _name: &str, _item: YjrItem) {
-        todo!()
+    // items of current target, sorted by key
+    pub fn items(&self) -> Vec<(&String, &YjrItem)> {
+        let mut ret: Vec<(&String, &YjrItem)> = self.maps[self.target].iter().collect();
+        ret.sort_by(|a, b| a.0.cmp(b.0));
+        ret
     }
 }
 
@@ -284,48 +337,161 @@ enum WordCode {
     SetOperator(),
     Native(String),
     User(String),
+    Sample(String),         // user word defined by #def~
+    Z1(),
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
-enum WordByte {
+pub(crate) enum WordByte {
     Number(TNT),
     Symbol(usize),
     GetOperator(),
     SetOperator(),
     Native(usize),
     User(usize),
+    Sample(usize, usize),   // binary and its state
+    Z1(usize),
 }
 
-type UserWord = Vec<WordCode>;
-type UserBinary = Vec<WordByte>;
+type UserWord = Vec<(WordCode, usize)>;     // with line number in source
+pub(crate) type UserBinary = Vec<WordByte>;
 type EnvConfig =  (i32, f32, bool);
+
+/// Named parameters of the runtime in build, keyed by full path of each
+/// word instance and `None` until set, see `YjrEnviroment::params`.
+pub type NamedParams = Rc<RefCell<HashMap<String, Option<TNT>>>>;
 pub trait NativeWord  {
     fn run(&mut self, stack: &mut YjrStack);
+
+    // called once at build with the constants pushed right before the word
+    // in its source, bottom first, to load resources out of the audio path
+    fn link(&mut self, _literals: &[YjrItem]) {}
+
+    // signal captured by probe words
+    fn probe(&self) -> Option<&Probe> {
+        None
+    }
+}
+
+/// Signal captured by a `probe~` word, accumulated across blocks up to
+/// the `ProbeLength` setting.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Probe {
+    pub name: String,
+    pub rate: i32,
+    pub data: Vec<TNT>,
+}
+
+impl Probe {
+    pub fn write_wav<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        wav::write(path, &[&self.data], self.rate)
+    }
+
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut txt = "index,value\n".to_string();
+        for (i, x) in self.data.iter().enumerate() {
+            txt.push_str( &format!("{},{}\n", i, x) );
+        }
+        fs::write(path, txt)
+    }
+}
+
+// state of a linked per-sample word
+struct SampleState {
+    arity: usize,
+    z1: TNT,                // last output
+    ov: Option<SharedVector>,
+}
+
+// result of compiling a source text
+struct Program {
+    main:   UserWord,
+    words:  Vec<String>,        // names of words defined by #def
+    loops:  Vec<UserWord>,      // #loop bodies before unrolling
 }
 
 pub struct YjrEnviroment {
     user_words: HashMap<String, UserWord >,
+    sample_words: HashMap<String, usize>,   // inputs of #def~ words
     native_words: HashMap<String, fn(&YjrEnviroment)->Box<dyn NativeWord> >,
+    native_effects: HashMap<String, StackEffect>,
     settings:   HashMap<String, EnvConfig>,
+    seeds:      Cell<u32>,      // seeds taken since last build
+    params:     NamedParams,
+    faust_words: HashMap<String, String>,   // JSON descriptions
 }
 
 impl YjrEnviroment {
     fn insert_user_word(&mut self, name: &str, word: UserWord) {
         self.user_words.insert(name.to_string(), word);
+        self.sample_words.remove(name);
     }
 
-    fn compile(&mut self, txt: &str) -> UserWord {
-        fn tokenize(expr: &str) -> Vec<String> {
-            expr.replace("\n", " ")
-                .replace("{", " ")
-                .replace("}", " ")
-                .replace("(", " ")
-                .replace(")", " ")
-                .replace("]", " ] ")
-                .replace("[", " [ ")
-                .split_whitespace()
-                .map(|x| x.to_string())
-                .collect()
+    // the body of a per-sample word takes numbers and leaves one sample,
+    // its inputs are counted by checking it on an open stack, then it is
+    // checked again with numbers as inputs.
+    fn insert_sample_word(&mut self, name: &str, word: UserWord) {
+        let path = || vec![ name.to_string() ];
+        let mut stack = TypeStack::open();
+        if let Err(e) = self.check_code(&word, &mut stack, &mut path()) {
+            panic!("Stack effect checking failed:\n{}", e);
+        }
+        if stack.is_lost() {
+            panic!("Per-sample word `{}` can only use words with stack effects", name);
+        }
+        let arity = stack.pulled();
+
+        let mut stack = TypeStack::new();
+        for _ in 0..arity {
+            stack.push( Ty::N(None) );
+        }
+        if let Err(e) = self.check_code(&word, &mut stack, &mut path()) {
+            panic!("Per-sample word `{}` must work on numbers:\n{}", name, e);
+        }
+        if stack.depth() != 1 || !matches!(stack.top(), Some(Ty::N(_))) {
+            panic!("Per-sample word `{}` must leave one sample, inferred stack is {}", name, stack);
+        }
+        self.insert_user_word(name, word);
+        self.sample_words.insert(name.to_string(), arity);
+    }
+
+    // vector inputs give the block length, a length comes first when all
+    // inputs are numbers. The inputs are read from the stack one by one,
+    // only unknown items give a second alternative.
+    fn sample_effect(arity: usize, stack: &TypeStack) -> StackEffect {
+        let inputs: Vec<&str> = (0..arity).rev().map(|depth| {
+            match stack.peek(depth) {
+                Some(Ty::V) => "v",
+                Some(Ty::Any) => "x",
+                _ => "n",
+            }
+        }).collect();
+        let inputs = inputs.join(" ");
+        let sig = if inputs.contains('v') {
+            format!("{} -- v", inputs)
+        } else if inputs.contains('x') {
+            format!("{} -- v | n {} -- v", inputs, inputs)
+        } else {
+            format!("n {} -- v", inputs)
+        };
+        StackEffect::parse(&sig)
+    }
+
+    fn compile(&mut self, txt: &str) -> Program {
+        fn tokenize(expr: &str) -> Vec<(String, usize)> {
+            let mut tokens = Vec::new();
+            for (i, line) in expr.lines().enumerate() {
+                let words = line.replace("{", " ")
+                    .replace("}", " ")
+                    .replace("(", " ")
+                    .replace(")", " ")
+                    .replace("]", " ] ")
+                    .replace("[", " [ ");
+                for w in words.split_whitespace() {
+                    tokens.push( (w.to_string(), i + 1) );
+                }
+            }
+            tokens
         }
 
         fn remove_comment(txt: &str) -> String {
@@ -343,9 +509,10 @@ impl YjrEnviroment {
             contents
         }
 
+        // names of words
         fn check_symbol(symbol: &str) -> bool {
             symbol.chars().all( |c| {
-                (c == '_') || matches!(c, 'a'..='z') || matches!(c, 'A'..='Z') || matches!(c, '0'..='9')
+                matches!(c, '_' | '.' | '/') || c.is_ascii_alphanumeric()
             })
         }
 
@@ -360,15 +527,19 @@ impl YjrEnviroment {
         let tokens = tokenize( &remove_comment(txt) );
 
         let mut main_code: UserWord = Vec::new();
+        let mut words: Vec<String> = Vec::new();
+        let mut loops: Vec<UserWord> = Vec::new();
         let mut word_code: Option<UserWord> = None;
+        let mut sample_def = false;             // word_code is a #def~
         let mut loop_code: Option<UserWord> = None;
         let mut list_count: Option<usize> = None;
 
-        for token in &tokens {
+        for (token, line) in &tokens {
             let token = token.as_str();
+            let line = *line;
 
             // first pass, processing command primitive
-            let mut new_code: WordCode = if token == "#def" {
+            let mut new_code: WordCode = if token == "#def" || token == "#def~" {
                 if !word_code.is_none() {
                     panic!("Can't define new word inside a word.");
                 }
@@ -379,6 +550,7 @@ impl YjrEnviroment {
                     panic!("Can't define new word in a list macro.");
                 }
                 word_code = Some( Vec::new() );
+                sample_def = token == "#def~";
                 continue;
             } else if token == "#loop" {
                 if !loop_code.is_none() || !list_count.is_none() {
@@ -396,7 +568,7 @@ impl YjrEnviroment {
                     if w.len() == 0 {
                         panic!("#loop macro without loop count!");
                     }
-                    if let WordCode::Number(ln) = w[0] {
+                    if let WordCode::Number(ln) = w[0].0 {
                         if ln.fract() != 0.0 {
                             panic!("Loop count must be a integer");
                         }
@@ -405,6 +577,7 @@ impl YjrEnviroment {
                                 main_code.push( w[i].clone() );
                             }
                         }
+                        loops.push( w[1..].to_vec() );
                     } else {
                         panic!("First item must be a number in #loop");
                     }
@@ -417,7 +590,7 @@ impl YjrEnviroment {
                     if w.len() == 0 {
                         panic!("#define macro without word name!");
                     }
-                    if let WordCode::Symbol(ref s) = w[0] {
+                    if let WordCode::Symbol(ref s) = w[0].0 {
                         if s.starts_with("$") {
                             panic!("Word's name can't begin with $");
                         }
@@ -425,7 +598,12 @@ impl YjrEnviroment {
                         for i in 1..w.len() {
                             new_word.push( w[i].clone() );
                         }
-                        self.insert_user_word(s, new_word);
+                        if sample_def {
+                            self.insert_sample_word(s, new_word);
+                        } else {
+                            self.insert_user_word(s, new_word);
+                        }
+                        words.push( s.clone() );
                     } else {
                         panic!("First item must be a word name in #define");
                     }
@@ -457,7 +635,7 @@ impl YjrEnviroment {
                 if let Some(ref mut w) = word_code {
                     if w.len() == 0 {
                         if check_symbol(&token) {
-                            w.push( WordCode::Symbol(token.to_string()) );
+                            w.push( (WordCode::Symbol(token.to_string()), line) );
                             continue;
                         } else {
                             panic!("Word name must be a alphabetnumberic");
@@ -474,13 +652,14 @@ impl YjrEnviroment {
             };
 
             // second pass: translate symbol to native or user word.
+            let in_sample = word_code.is_some() && sample_def;
             let mut push_code = |x: WordCode| {
                 if let Some(ref mut uw) = loop_code {
-                    uw.push(x);
+                    uw.push( (x, line) );
                 } else if let Some(ref mut uw) = word_code {
-                    uw.push(x);
+                    uw.push( (x, line) );
                 } else {
-                    main_code.push(x);
+                    main_code.push( (x, line) );
                 }
 
                 if let Some(n) = list_count {
@@ -488,7 +667,7 @@ impl YjrEnviroment {
                 }
             };
 
-            let mut symbol = match &new_code {
+            let symbol = match &new_code {
                 WordCode::Number(_) => {
                     push_code(new_code.clone());
                     continue;
@@ -527,6 +706,14 @@ impl YjrEnviroment {
                 push_code(new_code);
                 continue;
             }
+            if symbol == "z1" {
+                if !in_sample {
+                    panic!("z1 can only be used in a #def~ word");
+                }
+                new_code = WordCode::Z1();
+                push_code(new_code);
+                continue;
+            }
 
             // checking is a native word
             if self.native_words.get(&symbol).is_some() {
@@ -535,6 +722,16 @@ impl YjrEnviroment {
                 continue;
             }
 
+            // checking is a per-sample word
+            if self.sample_words.contains_key(&symbol) {
+                if in_sample {
+                    panic!("Can't use per-sample word `{}` in a #def~ word", symbol);
+                }
+                new_code = WordCode::Sample(symbol.clone());
+                push_code(new_code);
+                continue;
+            }
+
             // checking is a user word
             if self.user_words.get(&symbol).is_some() {
                 new_code = WordCode::User(symbol.clone());
@@ -542,12 +739,9 @@ impl YjrEnviroment {
                 continue;
             }
 
-            // checking is a valid symbol
+            // strings take any token, like a path `$C:/ir-1.wav`, only
+            // whitespace and brackets split them
             if symbol.starts_with("$") || symbol.starts_with("%") {
-                symbol.remove(0);
-                if !check_symbol( &symbol ) {
-                    panic!("Symbol must include alphanumbric or '_'");
-                }
                 push_code(new_code);
                 continue;
             }
@@ -559,7 +753,93 @@ impl YjrEnviroment {
             panic!("#loop list or word without ending");
         }
 
-        main_code
+        Program {
+            main: main_code,
+            words,
+            loops,
+        }
+    }
+
+    // simulating the stack through code, the first error is reported with
+    // the word and the path of user words leading to it.
+    fn check_code(&self, code: &UserWord, stack: &mut TypeStack, path: &mut Vec<String>) -> Result<(), String> {
+        for (c, _) in code {
+            let (name, effect) = match c {
+                WordCode::Number(n) => {
+                    stack.push( Ty::N(Some(*n)) );
+                    continue;
+                },
+                WordCode::Symbol(_) => {
+                    stack.push( Ty::S );
+                    continue;
+                },
+                WordCode::Z1() => {
+                    stack.push( Ty::N(None) );
+                    continue;
+                },
+                WordCode::Sample(s) => (s.as_str(), Self::sample_effect(self.sample_words[s], stack)),
+                WordCode::GetOperator() => ("@", StackEffect::parse("s -- x")),
+                WordCode::SetOperator() => ("!", StackEffect::parse("x s --")),
+                WordCode::Native(s) => {
+                    match self.native_effects.get(s) {
+                        Some(e) => (s.as_str(), e.clone()),
+                        None => {
+                            stack.lose();
+                            continue;
+                        }
+                    }
+                },
+                WordCode::User(s) => {
+                    path.push( s.clone() );
+                    self.check_code(self.get_user(s), stack, path)?;
+                    path.pop();
+                    continue;
+                },
+            };
+
+            if let Err(e) = effect.apply(stack) {
+                let mut msg = format!("word `{}`", name);
+                for w in path.iter().rev() {
+                    msg.push_str( &format!(" in `{}`", w) );
+                }
+                return Err( format!("{}: {}", msg, e) );
+            }
+        }
+        Ok(())
+    }
+
+    fn verify(&self, program: &Program) {
+        let mut errors: Vec<String> = Vec::new();
+
+        let mut stack = TypeStack::new();
+        if let Err(e) = self.check_code(&program.main, &mut stack, &mut Vec::new()) {
+            errors.push(e);
+        }
+
+        for name in &program.words {
+            let mut stack = TypeStack::open();
+            let mut path = vec![ name.clone() ];
+            if let Err(e) = self.check_code(self.get_user(name), &mut stack, &mut path) {
+                errors.push(e);
+            }
+        }
+
+        for body in &program.loops {
+            let mut stack = TypeStack::open();
+            match self.check_code(body, &mut stack, &mut vec!["#loop".to_string()]) {
+                Err(e) => errors.push(e),
+                Ok(_) => {
+                    if !stack.is_lost() && stack.depth() != 0 {
+                        errors.push( format!("#loop body is unbalanced, each pass changes stack depth by {}, inferred stack is {}",
+                                             stack.depth(), stack) );
+                    }
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            panic!("Stack effect checking failed:\n{}", errors.join("\n"));
+        }
     }
 
     fn create_native(&self, name: &str) -> Box<dyn NativeWord> {
@@ -581,14 +861,23 @@ impl YjrEnviroment {
     pub fn new(r: i32) -> Self {
         let mut env = YjrEnviroment {
             user_words: HashMap::new(),
+            sample_words: HashMap::new(),
             native_words: HashMap::new(),
-            settings: HashMap::new()
+            native_effects: HashMap::new(),
+            settings: HashMap::new(),
+            seeds: Cell::new(0),
+            params: NamedParams::default(),
+            faust_words: HashMap::new(),
         };
         env.settings.insert("SampleRate".to_string() , (r, 0.0, false));
+        env.settings.insert("ProbeLength".to_string() , (r, 0.0, false));
+        env.settings.insert("MaxDelay".to_string() , (0, 2.0, false));
+        env.settings.insert("Seed".to_string() , (0, 0.0, false));
 
         base::insert_native_words(&mut env);
         math::insert_native_words(&mut env);
         faust::insert_native_words(&mut env);
+        words::insert_native_words(&mut env);
         env
     }
 
@@ -599,69 +888,175 @@ impl YjrEnviroment {
         panic!("Can't find key in settings of enviroment");
     }
 
+    /// Changes a setting, native words read settings when a program is built.
+    pub fn config(&mut self, key: &str, value: EnvConfig) {
+        self.settings.insert(key.to_string(), value);
+    }
+
     pub fn insert_native_word(&mut self, name: &str, word: fn(&YjrEnviroment) -> Box<dyn NativeWord>) {
         self.native_words.insert(name.to_string(), word);
     }
 
+    /// Declares the stack effect of a native word, see `crate::verify`
+    /// for the signature syntax. Words without declared effect are not
+    /// checked, and the checker gives up on the stack after them.
+    pub fn insert_native_effect(&mut self, name: &str, effect: &str) {
+        self.native_effects.insert(name.to_string(), StackEffect::parse(effect));
+    }
+
+    /// Seed of the next random native word, mixed from the "Seed" setting
+    /// and the instance index in the built script.
+    pub fn seed(&self) -> u64 {
+        let i = self.seeds.get();
+        self.seeds.set(i + 1);
+
+        // splitmix64 finalizer
+        let mut z = ((self.query("Seed").0 as u32 as u64) << 32 | i as u64).wrapping_add(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Marks a native word as wrapped Faust dsp, `json` is its description
+    /// in the Faust JSON UI format.
+    pub fn insert_faust_description(&mut self, name: &str, json: &str) {
+        self.faust_words.insert(name.to_string(), json.to_string());
+    }
+
+    /// Every Faust word with its JSON description, sorted by name.
+    pub fn faust_words(&self) -> Vec<(&str, &str)> {
+        let mut words: Vec<(&str, &str)> = self.faust_words.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        words.sort();
+        words
+    }
+
+    /// Parameters shared by the native words of the runtime in build,
+    /// words with named parameters add their paths when created.
+    pub fn params(&self) -> NamedParams {
+        self.params.clone()
+    }
+
     pub fn build(&mut self, txt: &str ) -> YjrRuntime {
-        let main_code = self.compile(txt);
-        YjrRuntime::new(self, &main_code)
+        let program = self.compile(txt);
+        self.verify(&program);
+        self.seeds.set(0);
+        self.params = NamedParams::default();
+        YjrRuntime::new(self, &program.main)
     }
 }
 
+/// Source information of a linked word.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SrcInfo {
+    pub word: String,       // text of the word in source
+    pub line: usize,        // line number, starts from 1
+}
+
+/// How `YjrRuntime::run` executes the linked program.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Backend {
+    /// Decodes every `WordByte` of the binaries at each run.
+    Interpreter,
+    /// Runs the program compiled into closures, see `crate::threaded`.
+    /// The interpreter is still used while tracing or profiling.
+    Threaded,
+}
+
 pub struct YjrRuntime {
     pub stack:   YjrStack,
     pub hash:    YjrHash,
-    strings:     Vec< String>,
-    binarys:     Vec< UserBinary >,
-    natives:     Vec< Box<dyn NativeWord> >,
+    pub(crate) strings:     Vec< Rc<str> >,
+    pub(crate) binarys:     Vec< UserBinary >,
+    pub(crate) natives:     Vec< Box<dyn NativeWord> >,
+    samples:     Vec< SampleState >,
+    threaded:    Option< Thread >,      // code of main for Backend::Threaded
+    pub(crate) names:   Vec< String >,          // word name of each binary
+    pub(crate) infos:   Vec< Vec<SrcInfo> >,    // source of each word in binarys
+    trace:       Option< TraceHook >,
+    #[cfg(feature = "profile")]
+    profile:     Option< Profile >,
 }
 
 impl YjrRuntime {
     fn string_id(&mut self, s: &str) -> usize {
         for i in 0..self.strings.len() {
-            if s == &self.strings[i] {
+            if s == &*self.strings[i] {
                 return i;
             }
         }
         let ret = self.strings.len();
-        self.strings.push(s.to_string());
+        self.strings.push(s.into());
         ret
     }
 
-    fn linking(&mut self, env: &YjrEnviroment, main_code: &UserWord) {
+    // `sample` is the state of the word when it is a per-sample word
+    fn linking(&mut self, env: &YjrEnviroment, name: &str, main_code: &UserWord, sample: Option<usize>) {
         let id:usize = self.binarys.len();
         self.binarys.push( Vec::new());
+        self.infos.push( Vec::new());
+        self.names.push( name.to_string() );
         self.hash.inc();
 
         let mut bin = Vec::new();
-        for code in main_code {
-            match code {
+        let mut info = Vec::new();
+        let mut literals = Vec::new();
+        for (code, line) in main_code {
+            let word = match code {
                 WordCode::Number(n) => {
                     bin.push( WordByte::Number(*n) );
+                    n.to_string()
                 },
                 WordCode::Symbol(s) => {
                     bin.push( WordByte::Symbol(self.string_id(s)) );
+                    s.clone()
                 },
                 WordCode::GetOperator() => {
                     bin.push( WordByte::GetOperator());
+                    "@".to_string()
                 },
                 WordCode::SetOperator() => {
                     bin.push( WordByte::SetOperator());
+                    "!".to_string()
                 },
                 WordCode::Native(s) => {
                     bin.push( WordByte::Native( self.natives.len() ) );
-                    self.natives.push( env.create_native(s) );
+                    let mut native = env.create_native(s);
+                    native.link(&literals);
+                    self.natives.push( native );
+                    s.clone()
                 },
                 WordCode::User(s) => {
                     bin.push( WordByte::User( self.binarys.len() ) );
                     let uw = env.get_user(s);
-                    self.linking(env, uw);
+                    self.linking(env, s, uw, None);
+                    s.clone()
+                },
+                WordCode::Sample(s) => {
+                    let state = self.samples.len();
+                    self.samples.push( SampleState { arity: env.sample_words[s], z1: 0.0, ov: None } );
+                    bin.push( WordByte::Sample( self.binarys.len(), state ) );
+                    let uw = env.get_user(s);
+                    self.linking(env, s, uw, Some(state));
+                    s.clone()
+                },
+                WordCode::Z1() => {
+                    match sample {
+                        Some(state) => bin.push( WordByte::Z1(state) ),
+                        None => panic!("z1 can only be used in a #def~ word"),
+                    }
+                    "z1".to_string()
                 },
+            };
+            match code {
+                WordCode::Number(n) => literals.push( YjrItem::N(*n) ),
+                WordCode::Symbol(s) => literals.push( YjrItem::S(s.as_str().into()) ),
+                _ => literals.clear(),
             }
+            info.push( SrcInfo{ word, line: *line } );
         }
 
         self.binarys[id] = bin;
+        self.infos[id] = info;
     }
 
     fn new(env: &YjrEnviroment, main_code: &UserWord) -> Self {
@@ -671,45 +1066,249 @@ impl YjrRuntime {
             strings: Vec::new(),
             binarys: Vec::new(),
             natives: Vec::new(),
+            samples: Vec::new(),
+            threaded: None,
+            names: Vec::new(),
+            infos: Vec::new(),
+            trace: None,
+            #[cfg(feature = "profile")]
+            profile: None,
         };
 
-        rt.linking(env, main_code);
+        rt.linking(env, "main", main_code, None);
         rt
     }
 
+    // executes the j-th word of binary i, returns the binary to enter
+    // when the word is a user word.
+    pub(crate) fn exec(&mut self, i: usize, j: usize) -> Option<usize> {
+        match self.binarys[i][j] {
+            WordByte::Number(n) => {
+                self.stack.push_number(n);
+            },
+            WordByte::Symbol(s) => {
+                self.stack.push( YjrItem::S(self.strings[s].clone()) );
+            },
+            WordByte::GetOperator() => {
+                let key = self.stack.pop_string();
+                let item: YjrItem = self.hash.get(&key);
+                self.stack.push(item);
+            },
+            WordByte::SetOperator() => {
+                let key = self.stack.pop_string();
+                let item: YjrItem = self.stack.pop();
+                self.hash.set(&key, item);
+            },
+            WordByte::Native(n) => {
+                if let Some(hook) = self.trace.as_mut() {
+                    hook(Trace::Before(&self.infos[i][j]), &self.stack);
+                }
+                #[cfg(feature = "profile")]
+                let start = std::time::Instant::now();
+
+                self.natives[n].run(&mut self.stack);
+
+                #[cfg(feature = "profile")]
+                if let Some(p) = self.profile.as_mut() {
+                    p.record_native(n, start, &self.stack);
+                }
+                if let Some(hook) = self.trace.as_mut() {
+                    hook(Trace::After(&self.infos[i][j]), &self.stack);
+                }
+            },
+            WordByte::User(w) => {
+                return Some(w);
+            },
+            WordByte::Sample(w, state) => {
+                #[cfg(feature = "profile")]
+                let start = std::time::Instant::now();
+
+                self.run_sample(w, state, &|rt: &mut YjrRuntime| rt.run_(w));
+                self.hash.moveto(i);
+
+                #[cfg(feature = "profile")]
+                if let Some(p) = self.profile.as_mut() {
+                    p.record_user(w, start, &self.stack);
+                }
+            },
+            WordByte::Z1(state) => {
+                self.stack.push_number( self.z1(state) );
+            },
+        }
+        None
+    }
+
+    // last output of a per-sample word
+    pub(crate) fn z1(&self, state: usize) -> TNT {
+        self.samples[state].z1
+    }
+
+    // iterates a per-sample word over the block of its inputs, `body`
+    // runs the binary `w` once
+    pub(crate) fn run_sample(&mut self, w: usize, state: usize, body: &dyn Fn(&mut YjrRuntime)) {
+        let arity = self.samples[state].arity;
+        let n = self.stack.size();
+        if n < arity {
+            panic!("Per-sample word `{}` needs {} inputs", self.names[w], arity);
+        }
+        let mut size = None;
+        for item in &self.stack.items()[n - arity..] {
+            match item {
+                YjrItem::N(_) => {},
+                YjrItem::V(v) => {
+                    let l = v.vec().size();
+                    if size.is_some_and(|s| s != l) {
+                        panic!("Inputs of per-sample word `{}` must have same length!", self.names[w]);
+                    }
+                    size = Some(l);
+                },
+                _ => panic!("Inputs of per-sample word `{}` must be numbers or vectors!", self.names[w]),
+            }
+        }
+        let size = match size {
+            Some(s) => s,
+            None => {
+                match n.checked_sub(arity + 1).map(|k| &self.stack.items()[k]) {
+                    Some(YjrItem::N(c)) if c.fract() == 0.0 && *c >= 0.0 => *c as usize,
+                    _ => panic!("Per-sample word `{}` needs a length or input vectors!", self.names[w]),
+                }
+            },
+        };
+
+        let mut inputs: Vec<Control> = (0..arity).map(|_| Control::pop(&mut self.stack, size)).collect();
+        inputs.reverse();
+        if inputs.iter().all(|c| c.is_number()) {
+            self.stack.pop_number();
+        }
+        let inputs: Vec<Vec<TNT>> = inputs.iter().map(|c| c.values(size)).collect();
+
+        let out = output(&mut self.samples[state].ov, size);
+        for i in 0..size {
+            for x in inputs.iter() {
+                self.stack.push_number(x[i]);
+            }
+            body(self);
+            let y = self.stack.pop_number();
+            self.samples[state].z1 = y;
+            out.vec_mut()[i] = y;
+        }
+        self.stack.push_vector(out);
+    }
+
     fn run_(&mut self, i: usize) {
         self.hash.moveto(i);
         for j in 0..self.binarys[i].len() {
-            let w = self.binarys[i][j].clone();
-            match w {
-                WordByte::Number(n) => {
-                    self.stack.push_number(n);
-                },
-                WordByte::Symbol(s) => {
-                    self.stack.push_string( self.strings[s].to_string() );
-                },
-                WordByte::GetOperator() => {
-                    let key = self.stack.pop_string();
-                    let item: YjrItem = self.hash.get(&key);
-                    self.stack.push(item);
-                },
-                WordByte::SetOperator() => {
-                    let key = self.stack.pop_string();
-                    let item: YjrItem = self.stack.pop();
-                    self.hash.set(&key, item);
-                },
-                WordByte::Native(n) => {
-                    self.natives[n].run(&mut self.stack);
-                },
-                WordByte::User(w) => {
-                    assert!(w == (i + 1));
-                    self.run_(i+1);
-                },
+            if let Some(w) = self.exec(i, j) {
+                #[cfg(feature = "profile")]
+                let start = std::time::Instant::now();
+
+                self.run_(w);
+                self.hash.moveto(i);
+
+                #[cfg(feature = "profile")]
+                if let Some(p) = self.profile.as_mut() {
+                    p.record_user(w, start, &self.stack);
+                }
             }
         }
     }
     pub fn run(&mut self) {
-        self.run_(0);
+        match self.threaded.clone() {
+            Some(code) if !self.observed() => {
+                threaded::run(self, &code);
+                self.hash.moveto(0);
+            },
+            _ => self.run_(0),
+        }
+    }
+
+    // tracing and profiling need the interpreter
+    fn observed(&self) -> bool {
+        #[cfg(feature = "profile")]
+        if self.profile.is_some() {
+            return true;
+        }
+        self.trace.is_some()
+    }
+
+    /// Selects how the program is run, compiling it for
+    /// `Backend::Threaded`. States of native words are kept.
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.threaded = match backend {
+            Backend::Interpreter => None,
+            Backend::Threaded => Some( threaded::compile(self, 0) ),
+        };
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self.threaded {
+            Some(_) => Backend::Threaded,
+            None => Backend::Interpreter,
+        }
+    }
+
+    /// Installs a hook called before and after every native word.
+    pub fn set_trace(&mut self, hook: TraceHook) {
+        self.trace = Some(hook);
+    }
+
+    pub fn remove_trace(&mut self) {
+        self.trace = None;
+    }
+
+    /// Starts recording call count and time of every word, previous
+    /// records are dropped.
+    #[cfg(feature = "profile")]
+    pub fn start_profile(&mut self) {
+        self.profile = Some( Profile::new(self.natives.len(), self.binarys.len()) );
+    }
+
+    #[cfg(feature = "profile")]
+    pub fn stop_profile(&mut self) {
+        self.profile = None;
+    }
+
+    /// Report of recorded runs, none when profiling is not started.
+    #[cfg(feature = "profile")]
+    pub fn profile(&self) -> Option<ProfileReport> {
+        let p = self.profile.as_ref()?;
+
+        let mut natives = vec![SrcInfo{ word: String::new(), line: 0 }; self.natives.len()];
+        let mut users = vec![None; self.binarys.len()];
+        for i in 0..self.binarys.len() {
+            for j in 0..self.binarys[i].len() {
+                match self.binarys[i][j] {
+                    WordByte::Native(n) => natives[n] = self.infos[i][j].clone(),
+                    WordByte::User(w) | WordByte::Sample(w, _) => users[w] = Some( self.infos[i][j].clone() ),
+                    _ => {},
+                }
+            }
+        }
+        Some( p.report(natives, users) )
+    }
+
+    /// Signals captured by `probe~` words, in program order.
+    pub fn probes(&self) -> Vec<&Probe> {
+        self.natives.iter().filter_map(|n| n.probe()).collect()
+    }
+
+    pub fn probe(&self, name: &str) -> Option<&Probe> {
+        self.natives.iter().filter_map(|n| n.probe()).find(|p| p.name == name)
+    }
+
+    /// Writes every probe into `dir` as `name.wav` and `name.csv`.
+    pub fn dump_probes<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        fs::create_dir_all(&dir)?;
+        for p in self.probes() {
+            p.write_wav( dir.as_ref().join(format!("{}.wav", p.name)) )?;
+            p.write_csv( dir.as_ref().join(format!("{}.csv", p.name)) )?;
+        }
+        Ok(())
+    }
+
+    /// Starts a step debugger on this runtime, see `crate::debug`.
+    pub fn debugger(&mut self) -> YjrDebugger<'_> {
+        YjrDebugger::new(self)
     }
 }
 
@@ -752,6 +1351,116 @@ mod tests {
         println!("{:?}", rt.stack);
     }
 
+    #[test]
+    fn check_stack_effect() {
+        let mut env = YjrEnviroment::new(41100);
+        let txt = "#def osc dsp.os.osc #end
+                   #def gain 0.5 swap * #end
+                   64 440 osc gain 64 zeros~ + dup sum swap drop
+                   #loop 2 64 ones~ + #end";
+        let mut rt = env.build(txt);
+        rt.run();
+        assert_eq!(rt.stack.size(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "word `+` in `gain`: stack underflow")]
+    fn check_stack_underflow() {
+        let mut env = YjrEnviroment::new(41100);
+        let txt = "#def gain + #end
+                   1.0 gain";
+        env.build(txt);
+    }
+
+    #[test]
+    #[should_panic(expected = "word `ones~`: type mismatch, expected ( n -- v ) but inferred stack is [ v ]")]
+    fn check_stack_mismatch() {
+        let mut env = YjrEnviroment::new(41100);
+        env.build("64 zeros~ ones~");
+    }
+
+    #[test]
+    #[should_panic(expected = "#loop body is unbalanced")]
+    fn check_stack_loop() {
+        let mut env = YjrEnviroment::new(41100);
+        env.build("1.0 #loop 3 dup #end");
+    }
+
+    #[test]
+    fn sample_words() {
+        let mut env = YjrEnviroment::new(41100);
+        let block = |rt: &mut super::YjrRuntime| {
+            rt.run();
+            rt.stack.pop().as_vector().vec().data().clone()
+        };
+
+        // one-pole lowpass, the state is kept across blocks
+        let mut rt = env.build("#def~ onepole z1 0.9 * swap 0.1 * + #end
+                                4 ones~ onepole");
+        let y: Vec<f32> = [block(&mut rt), block(&mut rt)].concat();
+        for (i, v) in y.iter().enumerate() {
+            assert!((v - (1.0 - 0.9f32.powi(i as i32 + 1))).abs() < 1.0e-6);
+        }
+
+        // generators take a length, numbers are held over the block
+        let mut rt = env.build("#def~ ramp z1 0.25 + #end
+                                #def~ mix * z1 + #end
+                                4 ramp 4 ones~ 0.5 mix");
+        assert_eq!(block(&mut rt), vec![0.5, 1.0, 1.5, 2.0]);
+        assert_eq!(rt.stack.pop().as_vector().vec().data(), &vec![0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(block(&mut rt), vec![2.5, 3.0, 3.5, 4.0]);
+        assert_eq!(rt.stack.pop().as_vector().vec().data(), &vec![1.25, 1.5, 1.75, 2.0]);
+
+        // every call has its own state
+        let mut rt = env.build("2 ramp 2 ramp +");
+        assert_eq!(block(&mut rt), vec![0.5, 1.0]);
+    }
+
+    #[test]
+    fn sample_many_inputs() {
+        // the signature is read from the stack, not listed for every input
+        let mut env = YjrEnviroment::new(41100);
+        let mut rt = env.build(&format!("#def~ total {} #end {} 2 ones~ total",
+                                        "+ ".repeat(23), "1 ".repeat(23)));
+        rt.run();
+        assert_eq!(rt.stack.pop().as_vector().vec().data(), &vec![24.0; 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "word `onepole`: type mismatch, expected ( n n -- v )")]
+    fn sample_string_input() {
+        let mut env = YjrEnviroment::new(41100);
+        env.build("#def~ onepole z1 0.9 * swap 0.1 * + #end 4 $x onepole");
+    }
+
+    #[test]
+    #[should_panic(expected = "z1 can only be used in a #def~ word")]
+    fn sample_z1() {
+        let mut env = YjrEnviroment::new(41100);
+        env.build("#def last z1 #end");
+    }
+
+    #[test]
+    #[should_panic(expected = "Per-sample word `both` must leave one sample")]
+    fn sample_outputs() {
+        let mut env = YjrEnviroment::new(41100);
+        env.build("#def~ both dup #end");
+    }
+
+    #[test]
+    #[should_panic(expected = "Per-sample word `f` must work on numbers")]
+    fn sample_vector_input() {
+        let mut env = YjrEnviroment::new(41100);
+        env.build("#def~ f 1000 0.7 lpf~ #end 64 ones~ f");
+    }
+
+    #[test]
+    #[should_panic(expected = "Per-sample word `g` must leave one sample, inferred stack is [ v ]")]
+    fn sample_vector_output() {
+        let mut env = YjrEnviroment::new(41100);
+        env.build("#def~ g 4 ones~ #end 64 g");
+    }
+
     #[test]
     fn simple_faust() {
         let mut env = YjrEnviroment::new(41100);
@@ -0,0 +1,158 @@
+//! Threaded code backend of the runtime.
+//!
+//! The linked binaries are compiled once into a flat sequence of closures.
+//! User words are inlined, each call site has its own binary anyway, and
+//! constants are built at compile time, a run of them is pushed by one op.
+//! Hash operators select the map of their binary themselves, so entering
+//! and leaving words costs nothing.
+
+use std::rc::Rc;
+
+use crate::runtime::{YjrRuntime, YjrItem, WordByte};
+
+pub(crate) type Op = Box<dyn Fn(&mut YjrRuntime)>;
+
+/// Compiled code of a binary.
+pub(crate) type Thread = Rc<[Op]>;
+
+// constants collected so far become one op
+fn flush(consts: &mut Vec<YjrItem>, ops: &mut Vec<Op>) {
+    if consts.is_empty() {
+        return;
+    }
+    let items: Box<[YjrItem]> = std::mem::take(consts).into();
+    ops.push( Box::new(move |rt: &mut YjrRuntime| rt.stack.push_items(&items)) );
+}
+
+fn compile_into(rt: &YjrRuntime, i: usize, ops: &mut Vec<Op>, consts: &mut Vec<YjrItem>) {
+    for byte in rt.binarys[i].iter() {
+        let op: Op = match *byte {
+            WordByte::Number(n) => {
+                consts.push( YjrItem::N(n) );
+                continue;
+            },
+            WordByte::Symbol(s) => {
+                consts.push( YjrItem::S(rt.strings[s].clone()) );
+                continue;
+            },
+            WordByte::GetOperator() => {
+                Box::new(move |rt: &mut YjrRuntime| {
+                    rt.hash.moveto(i);
+                    let key = rt.stack.pop_string();
+                    let item = rt.hash.get(&key);
+                    rt.stack.push(item);
+                })
+            },
+            WordByte::SetOperator() => {
+                Box::new(move |rt: &mut YjrRuntime| {
+                    rt.hash.moveto(i);
+                    let key = rt.stack.pop_string();
+                    let item = rt.stack.pop();
+                    rt.hash.set(&key, item);
+                })
+            },
+            WordByte::Native(n) => {
+                Box::new(move |rt: &mut YjrRuntime| rt.natives[n].run(&mut rt.stack))
+            },
+            WordByte::User(w) => {
+                compile_into(rt, w, ops, consts);
+                continue;
+            },
+            WordByte::Sample(w, state) => {
+                let body = compile(rt, w);
+                Box::new(move |rt: &mut YjrRuntime| {
+                    rt.run_sample(w, state, &|rt: &mut YjrRuntime| run(rt, &body));
+                })
+            },
+            WordByte::Z1(state) => {
+                Box::new(move |rt: &mut YjrRuntime| {
+                    let y = rt.z1(state);
+                    rt.stack.push_number(y);
+                })
+            },
+        };
+        flush(consts, ops);
+        ops.push(op);
+    }
+}
+
+/// Compiles binary `i` with the user words it calls.
+pub(crate) fn compile(rt: &YjrRuntime, i: usize) -> Thread {
+    let mut ops = Vec::new();
+    let mut consts = Vec::new();
+    compile_into(rt, i, &mut ops, &mut consts);
+    flush(&mut consts, &mut ops);
+    ops.into()
+}
+
+pub(crate) fn run(rt: &mut YjrRuntime, code: &Thread) {
+    for op in code.iter() {
+        op(rt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::runtime::{YjrEnviroment, Backend};
+
+    // both backends leave the same stacks and hashes, block after block
+    fn differential(script: &str) {
+        let mut env = YjrEnviroment::new(44100);
+        let mut a = env.build(script);
+        let mut b = env.build(script);
+        b.set_backend(Backend::Threaded);
+        assert_eq!(b.backend(), Backend::Threaded);
+
+        for block in 0..4 {
+            a.run();
+            b.run();
+            assert_eq!(a.stack.items(), b.stack.items(), "'{}' at block {}", script, block);
+            assert_eq!(a.hash.items(), b.hash.items(), "'{}' at block {}", script, block);
+            a.stack.clear();
+            b.stack.clear();
+        }
+    }
+
+    #[test]
+    fn same_stacks() {
+        let scripts = [
+            "3.14 1.0 + floor ones~",
+            "1.5 $a ! $a @ $a @ * $b ! $b @ $a @",
+            "#def twice dup $t ! $t @ + #end 2 twice 64 ones~ twice $t @",
+            "#def osc dsp.os.osc #end #def gain 0.5 swap * #end 64 440 osc gain #loop 2 64 ones~ + #end",
+            "64 220 dsp.os.osc 64 330 dsp.os.osc 2 channels~ 3 swap * tanh",
+            "64 dsp.no.noise 2000 0.7071 lpf~ 64 pink~ 0.01 300 dsp.no.noise * conv~",
+            "64 220 dsp.os.osc 128 32 $hann stft~ 0.5 swap * 128 32 $hann istft~",
+            "512 dsp.no.noise 0.8 0.3 0.5 reverb~ 64 2000 velvet~",
+            "#def~ onepole z1 0.9 * swap 0.1 * + #end #def~ ramp z1 0.25 + dup $r ! #end 64 dsp.no.noise onepole 4 ramp",
+            "0.5 64 dsp.no.noise dup prev~ + * 0.1 accum 64 ones~ last",
+            "#def pair $k 2 #end 1 $a pair $b ! 3 pair swap drop + $b @ $k @",
+        ];
+        for s in scripts {
+            differential(s);
+        }
+    }
+
+    #[test]
+    fn trace_uses_interpreter() {
+        let mut env = YjrEnviroment::new(44100);
+        let mut rt = env.build("64 ones~ 64 zeros~ +");
+        rt.set_backend(Backend::Threaded);
+        let count = Rc::new(Cell::new(0));
+        let c = count.clone();
+        rt.set_trace( Box::new(move |_, _| c.set(c.get() + 1)) );
+        rt.run();
+        assert_eq!(count.get(), 6);
+
+        rt.remove_trace();
+        rt.run();
+        assert_eq!(count.get(), 6);
+        assert_eq!(rt.stack.size(), 2);
+
+        rt.set_backend(Backend::Interpreter);
+        assert_eq!(rt.backend(), Backend::Interpreter);
+    }
+}
@@ -0,0 +1,138 @@
+//! Circular delay line.
+//!
+//! A delay of `d` reads the sample pushed `d` pushes ago, so `read(0)` is
+//! the newest sample.
+
+use libnum::{Float, FromPrimitive};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct DelayLine<T> {
+    buf: Vec<T>,
+    pos: usize,         // next write
+}
+
+impl<T: Float + FromPrimitive> DelayLine<T> {
+    /// Creates a line holding delays up to `capacity` samples.
+    pub fn new(capacity: usize) -> DelayLine<T> {
+        DelayLine {
+            buf: vec![T::zero(); capacity + 3],
+            pos: 0,
+        }
+    }
+
+    /// The longest delay of every read method.
+    pub fn capacity(&self) -> usize {
+        self.buf.len() - 3
+    }
+
+    pub fn clear(&mut self) {
+        for x in self.buf.iter_mut() {
+            *x = T::zero();
+        }
+    }
+
+    pub fn push(&mut self, x: T) {
+        self.buf[self.pos] = x;
+        self.pos = (self.pos + 1) % self.buf.len();
+    }
+
+    pub fn read(&self, d: usize) -> T {
+        let n = self.buf.len();
+        self.buf[(self.pos + n - 1 - d.min(n - 1)) % n]
+    }
+
+    fn split(&self, d: T) -> (usize, T) {
+        let d = d.max(T::zero()).min( T::from_usize(self.capacity()).unwrap() );
+        let i = d.floor();
+        (i.to_usize().unwrap(), d - i)
+    }
+
+    pub fn read_linear(&self, d: T) -> T {
+        let (i, f) = self.split(d);
+        if f == T::zero() {
+            return self.read(i);
+        }
+        self.read(i) + (self.read(i + 1) - self.read(i)) * f
+    }
+
+    /// Third order Lagrange interpolation, linear for delays below one.
+    pub fn read_lagrange(&self, d: T) -> T {
+        let (i, f) = self.split(d);
+        if i == 0 {
+            return self.read_linear(d);
+        }
+        let one = T::one();
+        let two = T::from_f64(2.0).unwrap();
+        let six = T::from_f64(6.0).unwrap();
+        // points at delays i-1, i, i+1, i+2 with fraction f from i
+        let (x0, x1, x2, x3) = (self.read(i - 1), self.read(i), self.read(i + 1), self.read(i + 2));
+        let (d0, d1, d2, d3) = (f + one, f, f - one, f - two);
+        - x0 * d1 * d2 * d3 / six
+            + x1 * d0 * d2 * d3 / two
+            - x2 * d0 * d1 * d3 / two
+            + x3 * d0 * d1 * d2 / six
+    }
+
+    /// First order allpass interpolation, `state` is the last output of
+    /// this reader. Best for fixed or slowly changing delays.
+    pub fn read_allpass(&self, d: T, state: &mut T) -> T {
+        let (i, f) = self.split(d);
+        if f == T::zero() {
+            *state = self.read(i);
+            return *state;
+        }
+        let eta = (T::one() - f) / (T::one() + f);
+        *state = self.read(i + 1) + eta * (self.read(i) - *state);
+        *state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vector::delay::DelayLine;
+
+    #[test]
+    fn integer_reads() {
+        let mut line = DelayLine::<f64>::new(4);
+        assert_eq!(line.capacity(), 4);
+        for i in 1..=10 {
+            line.push(i as f64);
+        }
+        assert_eq!(line.read(0), 10.0);
+        assert_eq!(line.read(3), 7.0);
+        assert_eq!(line.read(4), 6.0);
+
+        line.clear();
+        assert_eq!(line.read(0), 0.0);
+    }
+
+    #[test]
+    fn fractional_reads() {
+        // a ramp is interpolated exactly by linear and Lagrange reads
+        let mut line = DelayLine::<f64>::new(16);
+        for i in 0..40 {
+            line.push(i as f64);
+        }
+        assert!((line.read_linear(2.25) - 36.75).abs() < 1e-12);
+        assert!((line.read_lagrange(2.25) - 36.75).abs() < 1e-12);
+        assert!((line.read_lagrange(0.5) - 38.5).abs() < 1e-12);
+
+        // cubic is exact for Lagrange only
+        let mut line = DelayLine::<f64>::new(16);
+        for i in 0..20 {
+            line.push((i as f64).powi(3));
+        }
+        assert!((line.read_lagrange(3.5) - 15.5f64.powi(3)).abs() < 1e-9);
+        assert!((line.read_linear(3.5) - 15.5f64.powi(3)).abs() > 1.0);
+
+        // a slow sine through allpass reads settles near the delayed value
+        let mut line = DelayLine::<f64>::new(16);
+        let mut state = 0.0;
+        let mut y = 0.0;
+        for i in 0..2000 {
+            line.push((i as f64 * 0.01).sin());
+            y = line.read_allpass(5.5, &mut state);
+        }
+        assert!((y - (1993.5 * 0.01f64).sin()).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,254 @@
+//! Fast Fourier transform.
+//!
+//! Mixed radix Cooley-Tukey for any size, with radix 2 and 4 butterflies
+//! and a generic one for other prime factors.
+
+use std::f64::consts::PI;
+use libnum::{Float, FromPrimitive, Zero};
+
+pub use libnum::Complex;
+
+/// FFT plan of a fixed size, with the work buffers of its transforms.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FFT<T> {
+    size: usize,
+    factors: Vec<usize>,
+    twiddles: Vec<Complex<T>>,      // e^{-2*pi*i*k/size}
+    input: Vec<Complex<T>>,         // copy of the data being transformed
+    scratch: Vec<Complex<T>>,       // for the generic butterfly
+}
+
+impl<T: Float + FromPrimitive> FFT<T> {
+    pub fn new(size: usize) -> FFT<T> {
+        assert!(size > 0, "FFT size must be positive");
+
+        let mut factors = Vec::new();
+        let mut n = size;
+        for p in [4, 2, 3, 5] {
+            while n.is_multiple_of(p) {
+                factors.push(p);
+                n /= p;
+            }
+        }
+        let mut p = 7;
+        while n > 1 {
+            while n.is_multiple_of(p) {
+                factors.push(p);
+                n /= p;
+            }
+            p += 2;
+        }
+
+        let twiddles = (0..size).map(|k| {
+            let a = -2.0 * PI * (k as f64) / (size as f64);
+            Complex::new( T::from_f64(a.cos()).unwrap(), T::from_f64(a.sin()).unwrap() )
+        }).collect();
+
+        let radix = factors.iter().cloned().max().unwrap_or(1);
+        FFT {
+            size,
+            factors,
+            twiddles,
+            input: vec![Complex::zero(); size],
+            scratch: vec![Complex::zero(); radix],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Forward transform in place, without scaling.
+    pub fn forward(&mut self, data: &mut [Complex<T>]) {
+        assert_eq!(data.len(), self.size, "FFT data must have size of the plan");
+        // taken out while the plan is borrowed by transform
+        let mut input = std::mem::take(&mut self.input);
+        let mut scratch = std::mem::take(&mut self.scratch);
+        input.copy_from_slice(data);
+        self.transform(&input, 1, data, 0, &mut scratch);
+        self.input = input;
+        self.scratch = scratch;
+    }
+
+    /// Inverse transform in place, scaled by 1/size.
+    pub fn inverse(&mut self, data: &mut [Complex<T>]) {
+        for x in data.iter_mut() {
+            *x = x.conj();
+        }
+        self.forward(data);
+        let s = T::one() / T::from_usize(self.size).unwrap();
+        for x in data.iter_mut() {
+            *x = x.conj() * s;
+        }
+    }
+
+    // decimation in time, `input` is read with `stride`, the sub transform
+    // of size n uses twiddles W_n^j = W_size^(j*size/n).
+    fn transform(&self, input: &[Complex<T>], stride: usize, output: &mut [Complex<T>],
+                 level: usize, scratch: &mut [Complex<T>]) {
+        let n = output.len();
+        if n == 1 {
+            output[0] = input[0];
+            return;
+        }
+
+        let p = self.factors[level];
+        let m = n / p;
+        for q in 0..p {
+            self.transform(&input[q*stride..], stride*p, &mut output[q*m..(q+1)*m], level + 1, scratch);
+        }
+
+        let ts = self.size / n;
+        match p {
+            2 => {
+                for k in 0..m {
+                    let a = output[k];
+                    let b = output[k + m] * self.twiddles[k * ts];
+                    output[k] = a + b;
+                    output[k + m] = a - b;
+                }
+            },
+            4 => {
+                for k in 0..m {
+                    let t0 = output[k];
+                    let t1 = output[k + m] * self.twiddles[k * ts];
+                    let t2 = output[k + 2*m] * self.twiddles[2 * k * ts];
+                    let t3 = output[k + 3*m] * self.twiddles[3 * k * ts];
+
+                    let a = t0 + t2;
+                    let b = t0 - t2;
+                    let c = t1 + t3;
+                    // -i * (t1 - t3)
+                    let d = t1 - t3;
+                    let d = Complex::new(d.im, -d.re);
+
+                    output[k] = a + c;
+                    output[k + m] = b + d;
+                    output[k + 2*m] = a - c;
+                    output[k + 3*m] = b - d;
+                }
+            },
+            _ => {
+                let tp = self.size / p;
+                for k in 0..m {
+                    for (q, s) in scratch.iter_mut().take(p).enumerate() {
+                        *s = output[q*m + k] * self.twiddles[q * k * ts];
+                    }
+                    for r in 0..p {
+                        let mut sum = scratch[0];
+                        for (q, s) in scratch.iter().enumerate().take(p).skip(1) {
+                            sum = sum + s * self.twiddles[((q * r) % p) * tp];
+                        }
+                        output[k + r*m] = sum;
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Window functions for spectral analysis, periodic form.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Window {
+    Rect,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl Window {
+    pub fn from_name(name: &str) -> Option<Window> {
+        match name {
+            "rect" => Some(Window::Rect),
+            "hann" => Some(Window::Hann),
+            "hamming" => Some(Window::Hamming),
+            "blackman" => Some(Window::Blackman),
+            _ => None,
+        }
+    }
+
+    pub fn coefficients<T: Float + FromPrimitive>(&self, size: usize) -> Vec<T> {
+        (0..size).map(|i| {
+            let x = 2.0 * PI * (i as f64) / (size as f64);
+            let w = match self {
+                Window::Rect => 1.0,
+                Window::Hann => 0.5 - 0.5 * x.cos(),
+                Window::Hamming => 0.54 - 0.46 * x.cos(),
+                Window::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+            };
+            T::from_f64(w).unwrap()
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use crate::vector::fft::{FFT, Complex, Window};
+
+    fn dft(x: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        let n = x.len();
+        (0..n).map(|k| {
+            let mut s = Complex::new(0.0, 0.0);
+            for (j, v) in x.iter().enumerate() {
+                let a = -2.0 * PI * ((j * k) % n) as f64 / n as f64;
+                s += v * Complex::new(a.cos(), a.sin());
+            }
+            s
+        }).collect()
+    }
+
+    fn signal(n: usize) -> Vec<Complex<f64>> {
+        (0..n).map(|i| {
+            let t = i as f64;
+            Complex::new( (0.37 * t).sin() + 0.25 * (1.3 * t).cos(), (0.11 * t * t).sin() )
+        }).collect()
+    }
+
+    #[test]
+    fn fft_against_dft() {
+        for n in [1, 2, 3, 4, 5, 6, 7, 8, 9, 12, 15, 16, 30, 49, 64, 77, 100, 128, 243, 256, 1000, 1024] {
+            let x = signal(n);
+            let expected = dft(&x);
+            let mut y = x.clone();
+            FFT::new(n).forward(&mut y);
+            for k in 0..n {
+                assert!((y[k] - expected[k]).norm() < 1e-9 * n as f64, "size {} bin {}", n, k);
+            }
+        }
+    }
+
+    #[test]
+    fn fft_roundtrip() {
+        for n in [8, 60, 97, 512] {
+            let x = signal(n);
+            let mut plan = FFT::new(n);
+            let mut y = x.clone();
+            plan.forward(&mut y);
+            plan.inverse(&mut y);
+            for k in 0..n {
+                assert!((y[k] - x[k]).norm() < 1e-12 * n as f64);
+            }
+        }
+
+        // single precision
+        let x: Vec<Complex<f32>> = (0..48).map(|i| Complex::new((i as f32 * 0.3).sin(), 0.0)).collect();
+        let mut plan = FFT::<f32>::new(48);
+        let mut y = x.clone();
+        plan.forward(&mut y);
+        plan.inverse(&mut y);
+        for k in 0..48 {
+            assert!((y[k] - x[k]).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn windows() {
+        let w: Vec<f64> = Window::Hann.coefficients(4);
+        assert_eq!(w.len(), 4);
+        assert!((w[0] - 0.0).abs() < 1e-12);
+        assert!((w[2] - 1.0).abs() < 1e-12);
+        assert_eq!(Window::from_name("blackman"), Some(Window::Blackman));
+        assert_eq!(Window::from_name("kaiser"), None);
+    }
+}
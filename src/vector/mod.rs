@@ -6,6 +6,9 @@
 mod impl_ops;
 mod impl_vec;
 mod utils;
+pub mod fft;
+pub mod delay;
+pub mod resample;
 
 /// The Vector struct.
 ///
@@ -0,0 +1,73 @@
+//! Sample rate conversion.
+//!
+//! Band limited interpolation with a Blackman windowed sinc, the cutoff is
+//! lowered to the new Nyquist frequency when downsampling.
+
+use std::f64::consts::PI;
+
+use crate::TNT;
+
+// zero crossings of the kernel on each side
+const ZEROS: f64 = 16.0;
+
+fn blackman(x: f64) -> f64 {
+    // x in -1..1
+    0.42 + 0.5 * (PI * x).cos() + 0.08 * (2.0 * PI * x).cos()
+}
+
+/// Resamples `x` taken at rate `from` to rate `to`.
+pub fn resample(x: &[TNT], from: i32, to: i32) -> Vec<TNT> {
+    assert!(from > 0 && to > 0, "Sample rates must be positive");
+    if from == to || x.is_empty() {
+        return x.to_vec();
+    }
+
+    let ratio = to as f64 / from as f64;
+    let cutoff = ratio.min(1.0);
+    let width = ZEROS / cutoff;         // half length in input samples
+    let len = (x.len() as f64 * ratio).round() as usize;
+
+    (0..len).map(|n| {
+        let t = n as f64 / ratio;
+        let lo = (t - width).ceil().max(0.0) as usize;
+        let hi = ((t + width).floor() as usize).min(x.len() - 1);
+        let mut s = 0.0;
+        for (k, v) in x.iter().enumerate().take(hi + 1).skip(lo) {
+            let d = k as f64 - t;
+            let a = PI * cutoff * d;
+            let sinc = if a == 0.0 { 1.0 } else { a.sin() / a };
+            s += *v as f64 * cutoff * sinc * blackman(d / width);
+        }
+        s as TNT
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::vector::resample::resample;
+
+    fn sine(freq: f64, rate: i32, n: usize) -> Vec<TNT> {
+        (0..n).map(|i| (2.0 * std::f64::consts::PI * freq * i as f64 / rate as f64).sin() as TNT).collect()
+    }
+
+    #[test]
+    fn sines() {
+        for (from, to) in [(44100, 48000), (48000, 44100), (22050, 44100), (96000, 32000)] {
+            let y = resample(&sine(1000.0, from, 4000), from, to);
+            let n = (4000.0 * to as f64 / from as f64).round() as usize;
+            assert_eq!(y.len(), n);
+            let expected = sine(1000.0, to, n);
+            // away from the edges
+            for i in n / 4..3 * n / 4 {
+                assert!((y[i] - expected[i]).abs() < 1.0e-3, "{} -> {} at {}", from, to, i);
+            }
+        }
+
+        // above the new Nyquist frequency is removed
+        let y = resample(&sine(20000.0, 48000, 4800), 48000, 16000);
+        assert!(y[400..1200].iter().all(|v| v.abs() < 0.01));
+
+        assert_eq!(resample(&[1.0, 2.0], 8000, 8000), vec![1.0, 2.0]);
+    }
+}
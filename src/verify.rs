@@ -0,0 +1,400 @@
+//! Static stack-effect checking.
+//!
+//! Native words declare their stack effects with a small signature
+//! language, written bottom to top like Forth comments:
+//!
+//! ```text
+//! n n -- n | n v -- v | v v -- v
+//! ```
+//!
+//! `s`, `n`, `v` and `m` are string, number, vector and multichannel
+//! items, `x` matches anything, a capital letter is a type variable
+//! bound by the inputs, and `[n]`, `[v]` or `[s]` is a count-prefixed
+//! list built with `[ ]`.
+//! Alternatives are separated by `|`, every one fitting the stack is
+//! applied: their outputs are merged, items they disagree on become `?`,
+//! and the stack is lost when they take or leave different counts.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::TNT;
+
+/// Type of a stack item as seen by the checker.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Ty {
+    S,
+    N(Option<TNT>),     // number, with its value when known at compile time
+    V,
+    M,
+    Any,
+}
+
+impl Ty {
+    fn fits(&self, pat: &Ty) -> bool {
+        matches!((self, pat),
+                 (Ty::Any, _) | (_, Ty::Any) | (Ty::S, Ty::S) | (Ty::V, Ty::V) | (Ty::M, Ty::M) | (Ty::N(_), Ty::N(_)))
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ty::S => write!(f, "s"),
+            Ty::N(_) => write!(f, "n"),
+            Ty::V => write!(f, "v"),
+            Ty::M => write!(f, "m"),
+            Ty::Any => write!(f, "?"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Pattern {
+    Item(Ty),
+    Var(char),
+    List(Ty),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Alternative {
+    inputs: Vec<Pattern>,
+    outputs: Vec<Pattern>,
+}
+
+/// Parsed stack effect of a native word.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StackEffect {
+    alts: Vec<Alternative>,
+}
+
+impl StackEffect {
+    pub fn parse(sig: &str) -> Self {
+        fn pattern(token: &str) -> Pattern {
+            let item = |t: &str| -> Ty {
+                match t {
+                    "s" => Ty::S,
+                    "n" => Ty::N(None),
+                    "v" => Ty::V,
+                    "m" => Ty::M,
+                    "x" => Ty::Any,
+                    _ => panic!("Unknown item '{}' in stack effect", t),
+                }
+            };
+            if token.starts_with('[') && token.ends_with(']') {
+                return Pattern::List( item(&token[1..token.len()-1]) );
+            }
+            let mut chars = token.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                if c.is_ascii_uppercase() {
+                    return Pattern::Var(c);
+                }
+            }
+            Pattern::Item( item(token) )
+        }
+
+        let mut alts = Vec::new();
+        for alt in sig.split('|') {
+            let (ins, outs) = match alt.split_once("--") {
+                Some(v) => v,
+                None => panic!("Stack effect '{}' without '--'", sig),
+            };
+            let inputs: Vec<Pattern> = ins.split_whitespace().map(pattern).collect();
+            let outputs: Vec<Pattern> = outs.split_whitespace().map(pattern).collect();
+            for o in &outputs {
+                match o {
+                    Pattern::List(_) => panic!("List can't be output of stack effect '{}'", sig),
+                    Pattern::Var(c) if !inputs.contains(o) => {
+                        panic!("Variable {} is not bound in stack effect '{}'", c, sig);
+                    },
+                    _ => {},
+                }
+            }
+            alts.push( Alternative{ inputs, outputs } );
+        }
+        StackEffect { alts }
+    }
+
+    /// Applies the effect to a simulated stack, fails with a short
+    /// description when no alternative fits.
+    pub fn apply(&self, stack: &mut TypeStack) -> Result<(), String> {
+        if stack.lost {
+            return Ok(());
+        }
+
+        let mut matched: Vec<(usize, Vec<Ty>)> = Vec::new();
+        let mut underflow = false;
+        for alt in &self.alts {
+            match stack.matches(alt) {
+                Match::Fit(used, outputs) => matched.push( (used, outputs) ),
+                Match::Unknown => {
+                    stack.lose();
+                    return Ok(());
+                },
+                Match::Underflow => underflow = true,
+                Match::Mismatch => {},
+            }
+        }
+
+        if matched.is_empty() {
+            let what = if underflow { "stack underflow" } else { "type mismatch" };
+            return Err( format!("{}, expected ( {} ) but inferred stack is {}", what, self, stack) );
+        }
+
+        // several alternatives fit only when unknown items are involved,
+        // keep what they agree on.
+        let (used, mut outputs) = matched[0].clone();
+        for (u, o) in &matched[1..] {
+            if *u != used || o.len() != outputs.len() {
+                stack.lose();
+                return Ok(());
+            }
+            for i in 0..o.len() {
+                if o[i] != outputs[i] {
+                    outputs[i] = Ty::Any;
+                }
+            }
+        }
+
+        stack.take(used);
+        stack.items.extend(outputs);
+        Ok(())
+    }
+}
+
+impl fmt::Display for StackEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn item(f: &mut fmt::Formatter, p: &Pattern) -> fmt::Result {
+            match p {
+                Pattern::Item(t) => write!(f, "{}", t),
+                Pattern::Var(c) => write!(f, "{}", c),
+                Pattern::List(t) => write!(f, "[{}]", t),
+            }
+        }
+        for (i, alt) in self.alts.iter().enumerate() {
+            if i > 0 {
+                write!(f, " | ")?;
+            }
+            for p in &alt.inputs {
+                item(f, p)?;
+                write!(f, " ")?;
+            }
+            write!(f, "--")?;
+            for p in &alt.outputs {
+                write!(f, " ")?;
+                item(f, p)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Match {
+    Fit(usize, Vec<Ty>),
+    Underflow,
+    Mismatch,
+    Unknown,
+}
+
+/// The simulated stack.
+///
+/// An open stack has an unknown bottom, underflowing it pulls `?` items,
+/// this is used for checking the body of a word without its caller.
+/// A lost stack has seen a word without declared effect, nothing more
+/// can be said about it.
+#[derive(Debug, Clone)]
+pub struct TypeStack {
+    items:  Vec<Ty>,
+    open:   bool,
+    lost:   bool,
+    pulled: usize,
+}
+
+impl TypeStack {
+    pub fn new() -> Self {
+        TypeStack {
+            items: Vec::new(),
+            open: false,
+            lost: false,
+            pulled: 0,
+        }
+    }
+
+    pub fn open() -> Self {
+        let mut ts = TypeStack::new();
+        ts.open = true;
+        ts
+    }
+
+    pub fn push(&mut self, t: Ty) {
+        self.items.push(t);
+    }
+
+    pub fn lose(&mut self) {
+        self.lost = true;
+        self.items.clear();
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.lost
+    }
+
+    /// Net count of items pushed since the stack was created.
+    pub fn depth(&self) -> isize {
+        self.items.len() as isize - self.pulled as isize
+    }
+
+    pub fn top(&self) -> Option<Ty> {
+        self.items.last().copied()
+    }
+
+    /// Count of items taken below the bottom of an open stack.
+    pub fn pulled(&self) -> usize {
+        self.pulled
+    }
+
+    /// Item at `depth` from the top, `?` below an open stack.
+    pub fn peek(&self, depth: usize) -> Option<Ty> {
+        if depth < self.items.len() {
+            Some( self.items[ self.items.len() - 1 - depth ] )
+        } else if self.open {
+            Some(Ty::Any)
+        } else {
+            None
+        }
+    }
+
+    fn take(&mut self, n: usize) {
+        for _ in 0..n {
+            if self.items.pop().is_none() {
+                self.pulled += 1;
+            }
+        }
+    }
+
+    fn matches(&self, alt: &Alternative) -> Match {
+        let mut vars: HashMap<char, Ty> = HashMap::new();
+        let mut depth = 0;
+        for p in alt.inputs.iter().rev() {
+            let t = match self.peek(depth) {
+                Some(t) => t,
+                None => return Match::Underflow,
+            };
+            match p {
+                Pattern::Item(pt) => {
+                    if !t.fits(pt) {
+                        return Match::Mismatch;
+                    }
+                    depth += 1;
+                },
+                Pattern::Var(c) => {
+                    vars.insert(*c, t);
+                    depth += 1;
+                },
+                Pattern::List(pt) => {
+                    let count = match t {
+                        Ty::N(Some(c)) if c >= 0.0 && c.fract() == 0.0 => c as usize,
+                        Ty::N(_) | Ty::Any => return Match::Unknown,
+                        _ => return Match::Mismatch,
+                    };
+                    depth += 1;
+                    for _ in 0..count {
+                        match self.peek(depth) {
+                            Some(t) if t.fits(pt) => {},
+                            Some(_) => return Match::Mismatch,
+                            None => return Match::Underflow,
+                        }
+                        depth += 1;
+                    }
+                },
+            }
+        }
+
+        let outputs = alt.outputs.iter().map(|p| {
+            match p {
+                Pattern::Item(t) => *t,
+                Pattern::Var(c) => vars[c],
+                Pattern::List(_) => unreachable!(),
+            }
+        }).collect();
+        Match::Fit(depth, outputs)
+    }
+}
+
+impl fmt::Display for TypeStack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[")?;
+        if self.open {
+            write!(f, " ..")?;
+        }
+        for t in &self.items {
+            write!(f, " {}", t)?;
+        }
+        write!(f, " ]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::verify::{StackEffect, TypeStack, Ty};
+
+    #[test]
+    fn parse_effect() {
+        let e = StackEffect::parse("n n -- n | n v -- v | v v -- v");
+        assert_eq!(format!("{}", e), "n n -- n | n v -- v | v v -- v");
+
+        let e = StackEffect::parse("A B C -- B C A");
+        assert_eq!(format!("{}", e), "A B C -- B C A");
+
+        let e = StackEffect::parse("[n] n --  v");
+        assert_eq!(format!("{}", e), "[n] n -- v");
+    }
+
+    #[test]
+    fn apply_effect() {
+        let add = StackEffect::parse("n n -- n | n v -- v | v v -- v");
+
+        let mut ts = TypeStack::new();
+        ts.push(Ty::N(Some(1.0)));
+        ts.push(Ty::V);
+        add.apply(&mut ts).unwrap();
+        assert_eq!(format!("{}", ts), "[ v ]");
+
+        let mut ts = TypeStack::new();
+        ts.push(Ty::V);
+        ts.push(Ty::N(None));
+        let err = add.apply(&mut ts).unwrap_err();
+        assert!(err.starts_with("type mismatch"));
+
+        let mut ts = TypeStack::new();
+        let err = StackEffect::parse("n -- v").apply(&mut ts).unwrap_err();
+        assert!(err.starts_with("stack underflow"));
+
+        let mut ts = TypeStack::open();
+        add.apply(&mut ts).unwrap();
+        assert_eq!(ts.depth(), -1);
+        assert_eq!(format!("{}", ts), "[ .. ? ]");
+    }
+
+    #[test]
+    fn apply_list() {
+        let e = StackEffect::parse("[n] -- v");
+
+        let mut ts = TypeStack::new();
+        ts.push(Ty::N(Some(0.5)));
+        ts.push(Ty::N(Some(1.0)));
+        ts.push(Ty::N(Some(2.0)));
+        e.apply(&mut ts).unwrap();
+        assert_eq!(format!("{}", ts), "[ v ]");
+
+        let mut ts = TypeStack::new();
+        ts.push(Ty::N(Some(0.5)));
+        ts.push(Ty::N(Some(2.0)));
+        assert!(e.apply(&mut ts).is_err());
+
+        let mut ts = TypeStack::new();
+        ts.push(Ty::N(None));
+        e.apply(&mut ts).unwrap();
+        assert!(ts.is_lost());
+    }
+}
@@ -0,0 +1,176 @@
+//! Minimal WAV file support.
+//!
+//! Files are written as 32 bit float, interleaved by channel. Reading
+//! accepts 8, 16, 24 and 32 bit integer and 32 or 64 bit float samples.
+
+use std::fs::{self, File};
+use std::io::{self, Write, BufWriter};
+use std::path::Path;
+
+use crate::TNT;
+
+/// Writes channels of same length to a 32 bit float WAV file.
+pub fn write<P: AsRef<Path>>(path: P, channels: &[&[TNT]], sample_rate: i32) -> io::Result<()> {
+    let nch = channels.len();
+    let frames = if nch > 0 { channels[0].len() } else { 0 };
+    for c in channels {
+        assert_eq!(c.len(), frames, "All channels of a WAV file must have same length");
+    }
+
+    let data_size = (frames * nch * 4) as u32;
+    let mut w = BufWriter::new( File::create(path)? );
+
+    w.write_all(b"RIFF")?;
+    w.write_all(&(4 + (8 + 18) + (8 + 4) + 8 + data_size).to_le_bytes())?;
+    w.write_all(b"WAVE")?;
+
+    w.write_all(b"fmt ")?;
+    w.write_all(&18u32.to_le_bytes())?;
+    w.write_all(&3u16.to_le_bytes())?;                                  // IEEE float
+    w.write_all(&(nch as u16).to_le_bytes())?;
+    w.write_all(&(sample_rate as u32).to_le_bytes())?;
+    w.write_all(&(sample_rate as u32 * nch as u32 * 4).to_le_bytes())?;  // byte rate
+    w.write_all(&(nch as u16 * 4).to_le_bytes())?;                      // block align
+    w.write_all(&32u16.to_le_bytes())?;
+    w.write_all(&0u16.to_le_bytes())?;
+
+    w.write_all(b"fact")?;
+    w.write_all(&4u32.to_le_bytes())?;
+    w.write_all(&(frames as u32).to_le_bytes())?;
+
+    w.write_all(b"data")?;
+    w.write_all(&data_size.to_le_bytes())?;
+    for i in 0..frames {
+        for c in channels {
+            w.write_all(&c[i].to_le_bytes())?;
+        }
+    }
+    w.flush()
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn u16_at(b: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([b[i], b[i + 1]])
+}
+
+fn u32_at(b: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]])
+}
+
+/// Reads a WAV file, returns the channels and the sample rate.
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<(Vec<Vec<TNT>>, i32)> {
+    let b = fs::read(path)?;
+    if b.len() < 12 || &b[0..4] != b"RIFF" || &b[8..12] != b"WAVE" {
+        return Err( invalid("Not a RIFF WAVE file") );
+    }
+
+    let mut format = None;          // tag, channels, rate, bits
+    let mut data = None;
+    let mut pos = 12;
+    while pos + 8 <= b.len() {
+        let id = &b[pos..pos + 4];
+        let size = u32_at(&b, pos + 4) as usize;
+        let body = &b[pos + 8..(pos + 8 + size).min(b.len())];
+        if id == b"fmt " {
+            if body.len() < 16 {
+                return Err( invalid("WAV format chunk is too short") );
+            }
+            let mut tag = u16_at(body, 0);
+            if tag == 0xfffe && body.len() >= 26 {
+                // extensible, the sub format starts with the tag
+                tag = u16_at(body, 24);
+            }
+            format = Some( (tag, u16_at(body, 2) as usize, u32_at(body, 4) as i32, u16_at(body, 14)) );
+        } else if id == b"data" {
+            data = Some(body);
+        }
+        // chunks are padded to even length
+        pos += 8 + size + (size & 1);
+    }
+
+    let (tag, nch, rate, bits) = format.ok_or_else(|| invalid("WAV file has no format chunk"))?;
+    let data = data.ok_or_else(|| invalid("WAV file has no data chunk"))?;
+    if nch == 0 {
+        return Err( invalid("WAV file has no channels") );
+    }
+    let width = (bits as usize).div_ceil(8);
+    let sample = |s: &[u8]| -> Option<TNT> {
+        match (tag, bits) {
+            (1, 8) => Some( (s[0] as TNT - 128.0) / 128.0 ),
+            (1, 16) => Some( i16::from_le_bytes([s[0], s[1]]) as TNT / 32768.0 ),
+            (1, 24) => Some( (i32::from_le_bytes([0, s[0], s[1], s[2]]) >> 8) as TNT / 8388608.0 ),
+            (1, 32) => Some( i32::from_le_bytes([s[0], s[1], s[2], s[3]]) as TNT / 2147483648.0 ),
+            (3, 32) => Some( f32::from_le_bytes([s[0], s[1], s[2], s[3]]) as TNT ),
+            (3, 64) => Some( f64::from_le_bytes([s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7]]) as TNT ),
+            _ => None,
+        }
+    };
+    if sample(&[0; 8]).is_none() {
+        return Err( invalid(&format!("Unsupported WAV format {} with {} bits", tag, bits)) );
+    }
+
+    let mut channels = vec![Vec::new(); nch];
+    for frame in data.chunks_exact(width * nch) {
+        for (c, s) in channels.iter_mut().zip(frame.chunks_exact(width)) {
+            c.push( sample(s).unwrap() );
+        }
+    }
+    Ok( (channels, rate) )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use crate::wav;
+
+    #[test]
+    fn roundtrip() {
+        let path = std::env::temp_dir().join("yjriver_wav_roundtrip.wav");
+        let a = vec![0.0, 0.5, -0.25, 1.0];
+        let b = vec![0.1, 0.2, 0.3, 0.4];
+        wav::write(&path, &[&a, &b], 22050).unwrap();
+        let (channels, rate) = wav::read(&path).unwrap();
+        assert_eq!(rate, 22050);
+        assert_eq!(channels, vec![a, b]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn integer_samples() {
+        // 16 bit mono with an odd sized chunk before data
+        let mut b = Vec::new();
+        b.extend_from_slice(b"RIFF");
+        b.extend_from_slice(&0u32.to_le_bytes());
+        b.extend_from_slice(b"WAVE");
+        b.extend_from_slice(b"fmt ");
+        b.extend_from_slice(&16u32.to_le_bytes());
+        for v in [1u16, 1] {
+            b.extend_from_slice(&v.to_le_bytes());
+        }
+        b.extend_from_slice(&8000u32.to_le_bytes());
+        b.extend_from_slice(&16000u32.to_le_bytes());
+        for v in [2u16, 16] {
+            b.extend_from_slice(&v.to_le_bytes());
+        }
+        b.extend_from_slice(b"LIST");
+        b.extend_from_slice(&3u32.to_le_bytes());
+        b.extend_from_slice(&[1, 2, 3, 0]);
+        b.extend_from_slice(b"data");
+        b.extend_from_slice(&6u32.to_le_bytes());
+        for v in [0i16, 16384, -32768] {
+            b.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let path = std::env::temp_dir().join("yjriver_wav_pcm16.wav");
+        fs::write(&path, &b).unwrap();
+        let (channels, rate) = wav::read(&path).unwrap();
+        assert_eq!(rate, 8000);
+        assert_eq!(channels, vec![vec![0.0, 0.5, -1.0]]);
+        fs::remove_file(&path).unwrap();
+
+        assert!(wav::read(std::env::temp_dir().join("yjriver_no_such_file.wav")).is_err());
+    }
+}
@@ -0,0 +1,286 @@
+use crate::TNT;
+use crate::wav;
+use crate::vector::resample::resample;
+use crate::vector::fft::{FFT, Complex};
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, SharedVector};
+use crate::words::output;
+
+// longest kernel convolved directly
+const FIR_MAX: usize = 64;
+
+enum Engine {
+    Direct {
+        ir: Vec<TNT>,
+        buf: Vec<TNT>,          // last ir.len()-1 inputs followed by the block
+    },
+    // uniformly partitioned overlap-save, partitions of the block length
+    Partitioned {
+        plan: FFT<TNT>,
+        parts: Vec<Vec<Complex<TNT>>>,
+        fdl: Vec<Vec<Complex<TNT>>>,
+        pos: usize,
+        input: Vec<TNT>,        // last two blocks
+        acc: Vec<Complex<TNT>>,
+    },
+}
+
+/// Convolution of a stream of fixed length blocks with an impulse response,
+/// the output has no latency.
+pub struct Convolver {
+    block: usize,
+    engine: Engine,
+}
+
+impl Convolver {
+    pub fn new(ir: &[TNT], block: usize) -> Self {
+        assert!(block > 0, "Convolution block must not be empty");
+        let ir = if ir.is_empty() { vec![0.0] } else { ir.to_vec() };
+
+        if ir.len() <= FIR_MAX {
+            let buf = vec![0.0; ir.len() - 1 + block];
+            return Convolver {
+                block,
+                engine: Engine::Direct { ir, buf },
+            };
+        }
+
+        let mut plan = FFT::new(2 * block);
+        let parts: Vec<Vec<Complex<TNT>>> = ir.chunks(block).map(|h| {
+            let mut p = vec![Complex::new(0.0, 0.0); 2 * block];
+            for (i, v) in h.iter().enumerate() {
+                p[i] = Complex::new(*v, 0.0);
+            }
+            plan.forward(&mut p);
+            p
+        }).collect();
+        let fdl = vec![vec![Complex::new(0.0, 0.0); 2 * block]; parts.len()];
+
+        Convolver {
+            block,
+            engine: Engine::Partitioned {
+                plan,
+                parts,
+                fdl,
+                pos: 0,
+                input: vec![0.0; 2 * block],
+                acc: vec![Complex::new(0.0, 0.0); 2 * block],
+            },
+        }
+    }
+
+    pub fn block(&self) -> usize {
+        self.block
+    }
+
+    pub fn process(&mut self, x: &[TNT], y: &mut [TNT]) {
+        let b = self.block;
+        assert!(x.len() == b && y.len() == b, "Convolution block length can't be changed");
+
+        match &mut self.engine {
+            Engine::Direct { ir, buf } => {
+                let l = ir.len();
+                buf[l-1..].copy_from_slice(x);
+                for (n, out) in y.iter_mut().enumerate() {
+                    let mut s = 0.0;
+                    for (k, h) in ir.iter().enumerate() {
+                        s += h * buf[n + l - 1 - k];
+                    }
+                    *out = s;
+                }
+                buf.copy_within(b.., 0);
+            },
+            Engine::Partitioned { plan, parts, fdl, pos, input, acc } => {
+                input.copy_within(b.., 0);
+                input[b..].copy_from_slice(x);
+
+                let spectrum = &mut fdl[*pos];
+                for (s, v) in spectrum.iter_mut().zip(input.iter()) {
+                    *s = Complex::new(*v, 0.0);
+                }
+                plan.forward(spectrum);
+
+                let k = parts.len();
+                for a in acc.iter_mut() {
+                    *a = Complex::new(0.0, 0.0);
+                }
+                for (j, h) in parts.iter().enumerate() {
+                    let xs = &fdl[(*pos + k - j) % k];
+                    for ((a, x), h) in acc.iter_mut().zip(xs.iter()).zip(h.iter()) {
+                        *a += x * h;
+                    }
+                }
+                plan.inverse(acc);
+                for (out, a) in y.iter_mut().zip(acc[b..].iter()) {
+                    *out = a.re;
+                }
+                *pos = (*pos + 1) % k;
+            },
+        }
+    }
+}
+
+// v ir conv~ -> v, v $path conv~ -> v
+//
+// The impulse response is a vector or the path of a wav file, resampled to
+// the enviroment rate. It is only read once: a constant path is loaded at
+// build, otherwise the item of the first run is taken and later ones are
+// dropped.
+pub struct ConvWord {
+    rate: i32,
+    ir: Option<Vec<TNT>>,
+    conv: Option<Convolver>,
+    ov: Option<SharedVector>,
+}
+impl ConvWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( ConvWord {
+            rate: env.query("SampleRate").0,
+            ir: None,
+            conv: None,
+            ov: None,
+        })
+    }
+
+    fn load(&self, path: &str) -> Vec<TNT> {
+        let path = path.trim_start_matches(['$', '%']);
+        match wav::read(path) {
+            Ok((channels, rate)) if !channels.is_empty() => resample(&channels[0], rate, self.rate),
+            Ok(_) => panic!("Impulse response '{}' has no channel", path),
+            Err(e) => panic!("Can't load impulse response '{}': {}", path, e),
+        }
+    }
+}
+impl NativeWord for ConvWord {
+    fn link(&mut self, literals: &[YjrItem]) {
+        if let Some(YjrItem::S(path)) = literals.last() {
+            self.ir = Some( self.load(path) );
+        }
+    }
+
+    fn run(&mut self, stack: &mut YjrStack) {
+        let ir = stack.pop();
+        let x = stack.pop_vector();
+        let x = x.vec();
+
+        if self.conv.is_none() {
+            let ir = match (self.ir.take(), ir) {
+                (Some(h), _) => h,
+                (None, YjrItem::V(v)) => v.vec().data().clone(),
+                (None, YjrItem::S(path)) => self.load(&path),
+                _ => panic!("conv~ needs an impulse response vector or path!"),
+            };
+            self.conv = Some( Convolver::new(&ir, x.size()) );
+        }
+
+        let conv = self.conv.as_mut().unwrap();
+        if conv.block() != x.size() {
+            panic!("conv~ can't change block length from {} to {}", conv.block(), x.size());
+        }
+        let out = output(&mut self.ov, x.size());
+        conv.process(x.data(), out.vec_mut().mut_data());
+        stack.push_vector(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::wav;
+    use crate::runtime::YjrEnviroment;
+    use crate::words::convolution::Convolver;
+
+    fn noise(n: usize, seed: u32) -> Vec<TNT> {
+        let mut s = seed;
+        (0..n).map(|_| {
+            s = s.wrapping_mul(1103515245).wrapping_add(12345);
+            (s >> 8) as TNT / (1u32 << 23) as TNT - 1.0
+        }).collect()
+    }
+
+    fn direct(x: &[TNT], h: &[TNT]) -> Vec<TNT> {
+        (0..x.len()).map(|n| {
+            let mut s = 0.0f64;
+            for (k, v) in h.iter().enumerate().take(n + 1) {
+                s += *v as f64 * x[n - k] as f64;
+            }
+            s as TNT
+        }).collect()
+    }
+
+    #[test]
+    fn against_direct_convolution() {
+        let x = noise(4096, 7);
+        for (len, block) in [(1, 64), (17, 64), (64, 32), (65, 64), (300, 64), (1000, 128), (2000, 100), (5000, 256)] {
+            let h = noise(len, len as u32);
+            let expected = direct(&x, &h);
+
+            let mut conv = Convolver::new(&h, block);
+            let mut y = vec![0.0; block];
+            for (i, xb) in x.chunks(block).enumerate() {
+                if xb.len() < block {
+                    break;
+                }
+                conv.process(xb, &mut y);
+                for j in 0..block {
+                    let e = expected[i * block + j];
+                    assert!((y[j] - e).abs() < 1.0e-4 * (len as TNT).sqrt(), "ir {} block {} at {}", len, block, i * block + j);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn conv_word() {
+        let mut env = YjrEnviroment::new(44100);
+        let mut rt = env.build("8 ones~ 3 ones~ conv~");
+        rt.run();
+        assert_eq!(rt.stack.pop().as_vector().vec().data(), &vec![1.0, 2.0, 3.0, 3.0, 3.0, 3.0, 3.0, 3.0]);
+        rt.run();
+        assert_eq!(rt.stack.pop().as_vector().vec().data(), &vec![3.0; 8]);
+
+        // step response of a long kernel
+        let mut rt = env.build("1000 dsp.no.noise dup 64 ones~ swap conv~");
+        rt.run();
+        let y = rt.stack.pop().as_vector();
+        let h = rt.stack.pop().as_vector();
+        for i in 0..64 {
+            assert!((y.vec()[i] - h.vec().data()[..=i].iter().sum::<TNT>()).abs() < 1.0e-4);
+        }
+    }
+
+    #[test]
+    fn conv_wav_path() {
+        let path = std::env::temp_dir().join("yjriver_conv_ir.wav");
+        wav::write(&path, &[&[1.0, 0.5, 0.25]], 44100).unwrap();
+
+        let mut env = YjrEnviroment::new(44100);
+        let mut rt = env.build(&format!("8 ones~ ${} conv~", path.display()));
+        rt.run();
+        let y = rt.stack.pop().as_vector().vec().data().clone();
+        assert_eq!(&y[..4], &[1.0, 1.5, 1.75, 1.75]);
+
+        // loaded at build, not in the first run
+        std::fs::remove_file(&path).unwrap();
+        rt.run();
+        assert_eq!(rt.stack.pop().as_vector().vec().data(), &vec![1.75; 8]);
+    }
+
+    #[test]
+    fn conv_path_characters() {
+        let path = std::env::temp_dir().join("yjriver-conv_ir~1.wav");
+        wav::write(&path, &[&[0.5]], 44100).unwrap();
+
+        let mut env = YjrEnviroment::new(44100);
+        let mut rt = env.build(&format!("4 ones~ ${} conv~", path.display()));
+        rt.run();
+        assert_eq!(rt.stack.pop().as_vector().vec().data(), &vec![0.5; 4]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't load impulse response")]
+    fn conv_missing_wav() {
+        let mut env = YjrEnviroment::new(44100);
+        env.build("8 ones~ $/nonexistent/yjriver_ir.wav conv~");
+    }
+}
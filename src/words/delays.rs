@@ -0,0 +1,268 @@
+use crate::TNT;
+use crate::vector::delay::DelayLine;
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, SharedVector};
+use crate::words::{output, signal_size, Control};
+
+fn max_delay(env: &YjrEnviroment) -> usize {
+    let rate = env.query("SampleRate").0;
+    (env.query("MaxDelay").1 * rate as f32).ceil() as usize + 1
+}
+
+// v time delay~ -> v, time in seconds
+pub struct DelayWord {
+    rate: TNT,
+    line: DelayLine<TNT>,
+    ov: Option<SharedVector>,
+}
+impl DelayWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( DelayWord {
+            rate: env.query("SampleRate").0 as TNT,
+            line: DelayLine::new( max_delay(env) ),
+            ov: None,
+        })
+    }
+}
+impl NativeWord for DelayWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let size = signal_size(stack, 1, "delay~");
+        let time = Control::pop(stack, size).values(size);
+        let x = stack.pop_vector();
+
+        let out = output(&mut self.ov, size);
+        {
+            let x = x.vec();
+            let mut y = out.vec_mut();
+            for i in 0..size {
+                self.line.push(x[i]);
+                y[i] = self.line.read_lagrange(time[i] * self.rate);
+            }
+        }
+        stack.push_vector(out);
+    }
+}
+
+// v time feedback comb~ -> v
+//
+// Feedback comb, y[n] = x[n] + feedback * y[n - time].
+pub struct CombWord {
+    rate: TNT,
+    line: DelayLine<TNT>,
+    ov: Option<SharedVector>,
+}
+impl CombWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( CombWord {
+            rate: env.query("SampleRate").0 as TNT,
+            line: DelayLine::new( max_delay(env) ),
+            ov: None,
+        })
+    }
+}
+impl NativeWord for CombWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let size = signal_size(stack, 2, "comb~");
+        let g = Control::pop(stack, size).values(size);
+        let time = Control::pop(stack, size).values(size);
+        let x = stack.pop_vector();
+
+        let out = output(&mut self.ov, size);
+        {
+            let x = x.vec();
+            let mut y = out.vec_mut();
+            for i in 0..size {
+                // one sample is gone by the push
+                let d = self.line.read_linear(time[i] * self.rate - 1.0);
+                y[i] = x[i] + g[i] * d;
+                self.line.push(y[i]);
+            }
+        }
+        stack.push_vector(out);
+    }
+}
+
+// v time gain allpass~ -> v
+//
+// Schroeder allpass, w[n] = x[n] + gain * w[n - time] and
+// y[n] = w[n - time] - gain * w[n].
+pub struct AllpassWord {
+    rate: TNT,
+    line: DelayLine<TNT>,
+    ov: Option<SharedVector>,
+}
+impl AllpassWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( AllpassWord {
+            rate: env.query("SampleRate").0 as TNT,
+            line: DelayLine::new( max_delay(env) ),
+            ov: None,
+        })
+    }
+}
+impl NativeWord for AllpassWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let size = signal_size(stack, 2, "allpass~");
+        let g = Control::pop(stack, size).values(size);
+        let time = Control::pop(stack, size).values(size);
+        let x = stack.pop_vector();
+
+        let out = output(&mut self.ov, size);
+        {
+            let x = x.vec();
+            let mut y = out.vec_mut();
+            for i in 0..size {
+                let d = self.line.read_linear(time[i] * self.rate - 1.0);
+                let w = x[i] + g[i] * d;
+                y[i] = d - g[i] * w;
+                self.line.push(w);
+            }
+        }
+        stack.push_vector(out);
+    }
+}
+
+// Freeverb tunings at 44100 Hz
+const COMB_TUNINGS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_TUNINGS: [usize; 4] = [556, 441, 341, 225];
+const FIXED_GAIN: TNT = 0.015;
+const SCALE_WET: TNT = 3.0;
+const SCALE_DAMP: TNT = 0.4;
+const SCALE_ROOM: TNT = 0.28;
+const OFFSET_ROOM: TNT = 0.7;
+
+struct LowpassComb {
+    line: DelayLine<TNT>,
+    size: usize,
+    store: TNT,
+}
+
+// v room damp wet reverb~ -> v
+//
+// Mono Freeverb, parameters in 0..1, wet mixes reverb with dry signal.
+pub struct ReverbWord {
+    combs: Vec<LowpassComb>,
+    allpasses: Vec<(DelayLine<TNT>, usize)>,
+    ov: Option<SharedVector>,
+}
+impl ReverbWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        let scale = env.query("SampleRate").0 as f64 / 44100.0;
+        let length = |t: usize| ((t as f64 * scale).round() as usize).max(1);
+        Box::new( ReverbWord {
+            combs: COMB_TUNINGS.iter().map(|t| LowpassComb {
+                line: DelayLine::new(length(*t)),
+                size: length(*t),
+                store: 0.0,
+            }).collect(),
+            allpasses: ALLPASS_TUNINGS.iter().map(|t| (DelayLine::new(length(*t)), length(*t))).collect(),
+            ov: None,
+        })
+    }
+}
+impl NativeWord for ReverbWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let wet = stack.pop_number().clamp(0.0, 1.0);
+        let damp = stack.pop_number().clamp(0.0, 1.0) * SCALE_DAMP;
+        let feedback = stack.pop_number().clamp(0.0, 1.0) * SCALE_ROOM + OFFSET_ROOM;
+        let x = stack.pop_vector();
+        let size = x.vec().size();
+
+        let out = output(&mut self.ov, size);
+        {
+            let x = x.vec();
+            let mut y = out.vec_mut();
+            for i in 0..size {
+                let input = x[i] * FIXED_GAIN;
+
+                let mut s = 0.0;
+                for c in self.combs.iter_mut() {
+                    let d = c.line.read(c.size - 1);
+                    c.store = d * (1.0 - damp) + c.store * damp;
+                    c.line.push(input + c.store * feedback);
+                    s += d;
+                }
+                for (line, n) in self.allpasses.iter_mut() {
+                    let d = line.read(*n - 1);
+                    line.push(s + d * 0.5);
+                    s = d - s;
+                }
+
+                y[i] = s * wet * SCALE_WET + x[i] * (1.0 - wet);
+            }
+        }
+        stack.push_vector(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::vector::Vector;
+    use crate::runtime::{YjrEnviroment, NativeWord, SharedVector};
+    use crate::words::testing::drive;
+    use crate::words::delays::{DelayWord, CombWord, AllpassWord, ReverbWord};
+
+    // response of a word to an impulse, in blocks of 64 samples at 1000 Hz
+    fn impulse(word: fn(&YjrEnviroment) -> Box<dyn NativeWord>, params: &[TNT], length: usize) -> Vec<TNT> {
+        drive(word, 1000, 64, length, &|stack, start, block| {
+            let x = Vector::from_fn(block, |i| if start + i == 0 { 1.0 } else { 0.0 });
+            stack.push_vector( SharedVector::new(x) );
+            for p in params {
+                stack.push_number(*p);
+            }
+        })
+    }
+
+    #[test]
+    fn delay() {
+        let y = impulse(DelayWord::new, &[0.005], 64);
+        assert_eq!(y[5], 1.0);
+        assert_eq!(y.iter().sum::<TNT>(), 1.0);
+
+        // fractional delay between two samples
+        let y = impulse(DelayWord::new, &[0.0105], 64);
+        assert!(y[10] > 0.4 && y[11] > 0.4);
+        assert!((y.iter().sum::<TNT>() - 1.0).abs() < 1.0e-6);
+
+        // modulated time
+        let mut env = YjrEnviroment::new(1000);
+        let mut rt = env.build("8 ones~ 0.001 8 ones~ * 0.002 swap + delay~");
+        rt.run();
+        assert_eq!(rt.stack.pop().as_vector().vec().data(), &vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn comb() {
+        let y = impulse(CombWord::new, &[0.01, 0.5], 128);
+        assert_eq!(y[0], 1.0);
+        assert_eq!(y[10], 0.5);
+        assert_eq!(y[20], 0.25);
+        assert_eq!(y[70], 0.5f32.powi(7));
+        assert_eq!(y[15], 0.0);
+    }
+
+    #[test]
+    fn allpass() {
+        // impulse response keeps energy
+        let y = impulse(AllpassWord::new, &[0.007, 0.7], 2048);
+        assert_eq!(y[0], -0.7);
+        let energy: TNT = y.iter().map(|x| x * x).sum();
+        assert!((energy - 1.0).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn reverb() {
+        // dry only
+        let y = impulse(ReverbWord::new, &[0.5, 0.5, 0.0], 64);
+        assert_eq!(y[0], 1.0);
+        assert_eq!(y[1..].iter().map(|x| x.abs()).sum::<TNT>(), 0.0);
+
+        // the tail starts after the shortest comb and decays
+        let y = impulse(ReverbWord::new, &[0.9, 0.2, 1.0], 8192);
+        assert_eq!(y[0], 0.0);
+        let energy = |a: usize, b: usize| y[a..b].iter().map(|x| x * x).sum::<TNT>();
+        assert!(energy(0, 2048) > 0.0);
+        assert!(energy(6144, 8192) < energy(0, 2048) * 0.5);
+        assert!(y.iter().all(|x| x.is_finite()));
+    }
+}
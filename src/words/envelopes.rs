@@ -0,0 +1,326 @@
+use crate::TNT;
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, SharedVector};
+use crate::words::output;
+
+// exponential segments end at -60 dB of their distance to the target
+const EXP_END: TNT = 0.001;
+const EXP_OFF: TNT = 1.0e-5;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Stage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// ADSR state, retriggered at every rising edge of the gate from the
+/// current level.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Adsr {
+    rate: TNT,
+    exponential: bool,
+    stage: Stage,
+    level: TNT,
+    step: TNT,          // linear release slope
+    gate: bool,
+}
+
+impl Adsr {
+    pub fn new(rate: i32, exponential: bool) -> Self {
+        Adsr {
+            rate: rate as TNT,
+            exponential,
+            stage: Stage::Idle,
+            level: 0.0,
+            step: 0.0,
+            gate: false,
+        }
+    }
+
+    // samples of a segment, at least one
+    fn samples(&self, t: TNT) -> TNT {
+        (t * self.rate).max(1.0)
+    }
+
+    fn coef(&self, t: TNT) -> TNT {
+        (EXP_END.ln() / self.samples(t)).exp()
+    }
+
+    pub fn tick(&mut self, gate: bool, a: TNT, d: TNT, s: TNT, r: TNT) -> TNT {
+        if gate && !self.gate {
+            self.stage = Stage::Attack;
+        } else if !gate && self.gate && self.stage != Stage::Idle {
+            self.stage = Stage::Release;
+            self.step = self.level / self.samples(r);
+        }
+        self.gate = gate;
+
+        match self.stage {
+            Stage::Idle => {
+                self.level = 0.0;
+            },
+            Stage::Attack => {
+                if self.exponential {
+                    let target = 1.0 / (1.0 - EXP_END);
+                    self.level = target + (self.level - target) * self.coef(a);
+                } else {
+                    self.level += 1.0 / self.samples(a);
+                }
+                if self.level >= 1.0 {
+                    self.level = 1.0;
+                    self.stage = Stage::Decay;
+                }
+            },
+            Stage::Decay => {
+                if self.exponential {
+                    self.level = s + (self.level - s) * self.coef(d);
+                    if (self.level - s).abs() < EXP_OFF {
+                        self.stage = Stage::Sustain;
+                    }
+                } else {
+                    self.level -= (1.0 - s) / self.samples(d);
+                    if self.level <= s {
+                        self.stage = Stage::Sustain;
+                    }
+                }
+                if self.stage == Stage::Sustain {
+                    self.level = s;
+                }
+            },
+            Stage::Sustain => {
+                self.level = s;
+            },
+            Stage::Release => {
+                if self.exponential {
+                    self.level *= self.coef(r);
+                } else {
+                    self.level -= self.step;
+                }
+                if self.level <= EXP_OFF {
+                    self.level = 0.0;
+                    self.stage = Stage::Idle;
+                }
+            },
+        }
+        self.level
+    }
+}
+
+// gate attack decay sustain release adsr~ -> v
+//
+// Gate is on above zero, times in seconds, adsr~ has linear segments and
+// adsre~ exponential ones.
+pub struct AdsrWord {
+    adsr: Adsr,
+    ov: Option<SharedVector>,
+}
+impl AdsrWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( AdsrWord {
+            adsr: Adsr::new(env.query("SampleRate").0, false),
+            ov: None,
+        })
+    }
+    pub fn exponential(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( AdsrWord {
+            adsr: Adsr::new(env.query("SampleRate").0, true),
+            ov: None,
+        })
+    }
+}
+impl NativeWord for AdsrWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let r = stack.pop_number();
+        let s = stack.pop_number();
+        let d = stack.pop_number();
+        let a = stack.pop_number();
+        let gate = stack.pop_vector();
+        let gate = gate.vec();
+
+        let out = output(&mut self.ov, gate.size());
+        {
+            let mut y = out.vec_mut();
+            for (y, g) in y.iter_mut().zip(gate.iter()) {
+                *y = self.adsr.tick(*g > 0.0, a, d, s, r);
+            }
+        }
+        drop(gate);
+        stack.push_vector(out);
+    }
+}
+
+// gate [ level time ... ] env~ -> v
+//
+// Every rising edge of the gate starts linear ramps from the current level
+// to each level in its time, the last level is held.
+pub struct EnvWord {
+    rate: TNT,
+    level: TNT,
+    segment: usize,
+    from: TNT,
+    pos: TNT,
+    gate: bool,
+    ov: Option<SharedVector>,
+}
+impl EnvWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( EnvWord {
+            rate: env.query("SampleRate").0 as TNT,
+            level: 0.0,
+            segment: usize::MAX,
+            from: 0.0,
+            pos: 0.0,
+            gate: false,
+            ov: None,
+        })
+    }
+
+    fn tick(&mut self, gate: bool, points: &[TNT]) -> TNT {
+        if gate && !self.gate {
+            self.segment = 0;
+            self.from = self.level;
+            self.pos = 0.0;
+        }
+        self.gate = gate;
+
+        if self.segment < points.len() / 2 {
+            let (target, time) = (points[self.segment * 2], points[self.segment * 2 + 1]);
+            let length = (time * self.rate).max(1.0);
+            self.pos += 1.0;
+            if self.pos >= length {
+                self.level = target;
+                self.segment += 1;
+                self.from = target;
+                self.pos = 0.0;
+            } else {
+                self.level = self.from + (target - self.from) * self.pos / length;
+            }
+        }
+        self.level
+    }
+}
+impl NativeWord for EnvWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let points = stack.pop_number_list();
+        if !points.len().is_multiple_of(2) {
+            panic!("env~ needs a list of level and time pairs!");
+        }
+        let gate = stack.pop_vector();
+        let gate = gate.vec();
+
+        let out = output(&mut self.ov, gate.size());
+        {
+            let mut y = out.vec_mut();
+            for (y, g) in y.iter_mut().zip(gate.iter()) {
+                *y = self.tick(*g > 0.0, &points);
+            }
+        }
+        drop(gate);
+        stack.push_vector(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::vector::Vector;
+    use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, SharedVector};
+    use crate::words::testing::drive;
+    use crate::words::envelopes::{AdsrWord, EnvWord};
+
+    // runs a word on a gate in blocks of 64 samples at 1000 Hz, `on` are
+    // the gate on ranges
+    fn envelope(word: fn(&YjrEnviroment) -> Box<dyn NativeWord>, params: &[TNT], on: &[(usize, usize)], length: usize) -> Vec<TNT> {
+        drive(word, 1000, 64, length, &|stack, start, block| {
+            let gate = Vector::from_fn(block, |i| {
+                let t = start + i;
+                if on.iter().any(|(a, b)| t >= *a && t < *b) { 1.0 } else { 0.0 }
+            });
+            stack.push_vector( SharedVector::new(gate) );
+            for p in params {
+                stack.push_number(*p);
+            }
+        })
+    }
+
+    fn close(a: TNT, b: TNT) -> bool {
+        (a - b).abs() < 1.0e-5
+    }
+
+    #[test]
+    fn linear_adsr() {
+        let y = envelope(AdsrWord::new, &[0.01, 0.01, 0.5, 0.02], &[(3, 50)], 128);
+        assert_eq!(y[2], 0.0);
+        assert!(close(y[3], 0.1));
+        assert!(close(y[12], 1.0));
+        assert!(close(y[17], 0.75));
+        assert!(close(y[22], 0.5));
+        assert!(close(y[49], 0.5));
+        assert!(close(y[50], 0.475));
+        assert!(close(y[69], 0.0));
+        assert_eq!(y[100], 0.0);
+    }
+
+    #[test]
+    fn retrigger() {
+        // gate closes during attack and opens again during release, the
+        // level continues without jumps
+        let y = envelope(AdsrWord::new, &[0.02, 0.01, 0.5, 0.02], &[(0, 5), (12, 60), (64, 100)], 128);
+        assert!(close(y[4], 0.25));
+        assert!(close(y[5], 0.2375));
+        assert!(close(y[12], 0.2125));
+        for i in 1..128 {
+            assert!((y[i] - y[i-1]).abs() <= 0.05 + 1.0e-6, "jump at {}", i);
+        }
+        // sample accurate restart at the block border
+        assert!(close(y[63], 0.4));
+        assert!(close(y[64], 0.45));
+    }
+
+    #[test]
+    fn exponential_adsr() {
+        let y = envelope(AdsrWord::exponential, &[0.01, 0.02, 0.3, 0.03], &[(0, 60)], 256);
+        assert!(close(y[9], 1.0));
+        // concave attack
+        for i in 2..10 {
+            assert!(y[i] - y[i-1] < y[i-1] - y[i-2]);
+        }
+        assert!((y[29] - 0.3).abs() < 0.001);
+        assert!(close(y[59], 0.3));
+        assert!((y[89] - 0.3 * 0.001).abs() < 1.0e-4);
+        assert_eq!(y[200], 0.0);
+    }
+
+    #[test]
+    fn multi_segment() {
+        let env = YjrEnviroment::new(1000);
+        let mut w = EnvWord::new(&env);
+        let mut stack = YjrStack::new();
+        let gate = Vector::from_fn(64, |i| if i == 2 { 1.0 } else { 0.0 });
+        stack.push_vector( SharedVector::new(gate) );
+        stack.push_number_list( vec![1.0, 0.01, 0.5, 0.02, 0.0, 0.0] );
+        w.run(&mut stack);
+        let y = stack.pop_vector();
+        let y = y.vec();
+        assert_eq!(y[1], 0.0);
+        assert!(close(y[2], 0.1));
+        assert!(close(y[11], 1.0));
+        assert!(close(y[21], 0.75));
+        assert!(close(y[31], 0.5));
+        assert_eq!(y[32], 0.0);
+        assert_eq!(y[63], 0.0);
+    }
+
+    #[test]
+    fn modulation() {
+        let mut env = YjrEnviroment::new(44100);
+        let txt = "64 dsp.no.noise 100 2000 64 ones~ 0.01 0.01 0.5 0.1 adsr~ * + 0.707 lpf~
+                   64 ones~ [ 1 0.001 0 0.1 ] env~ *";
+        let mut rt = env.build(txt);
+        rt.run();
+        assert_eq!(rt.stack.size(), 1);
+        assert!(rt.stack.pop().as_vector().vec().iter().all(|x| x.is_finite()));
+    }
+}
@@ -0,0 +1,285 @@
+use std::f64::consts::PI;
+
+use crate::TNT;
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, SharedVector};
+use crate::words::{output, signal_size, Control};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BiquadKind {
+    Lowpass,
+    Highpass,
+    Bandpass,
+    Notch,
+    Allpass,
+    Peaking,
+    Lowshelf,
+    Highshelf,
+}
+
+impl BiquadKind {
+    pub fn has_gain(&self) -> bool {
+        matches!(self, BiquadKind::Peaking | BiquadKind::Lowshelf | BiquadKind::Highshelf)
+    }
+}
+
+/// RBJ cookbook biquad in transposed direct form II.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Biquad {
+    kind: BiquadKind,
+    rate: f64,
+    b: [f64; 3],
+    a: [f64; 2],            // normalized by a0
+    z: [f64; 2],
+    params: Option<(TNT, TNT, TNT)>,
+}
+
+impl Biquad {
+    pub fn new(kind: BiquadKind, rate: i32) -> Self {
+        Biquad {
+            kind,
+            rate: rate as f64,
+            b: [1.0, 0.0, 0.0],
+            a: [0.0, 0.0],
+            z: [0.0, 0.0],
+            params: None,
+        }
+    }
+
+    /// Computes coefficients for cutoff/center `freq` in Hz, `q` and `gain`
+    /// in dB, the gain is ignored by filters without one.
+    pub fn design(&mut self, freq: TNT, q: TNT, gain: TNT) {
+        if self.params == Some((freq, q, gain)) {
+            return;
+        }
+        self.params = Some((freq, q, gain));
+
+        let freq = (freq as f64).clamp(1.0e-3, self.rate * 0.4999);
+        let q = (q as f64).max(1.0e-3);
+        let w0 = 2.0 * PI * freq / self.rate;
+        let (sn, cs) = w0.sin_cos();
+        let alpha = sn / (2.0 * q);
+        let a = 10f64.powf(gain as f64 / 40.0);
+        let sa = 2.0 * a.sqrt() * alpha;
+
+        let (b, a) = match self.kind {
+            BiquadKind::Lowpass => ([(1.0 - cs) / 2.0, 1.0 - cs, (1.0 - cs) / 2.0], [1.0 + alpha, -2.0 * cs, 1.0 - alpha]),
+            BiquadKind::Highpass => ([(1.0 + cs) / 2.0, -(1.0 + cs), (1.0 + cs) / 2.0], [1.0 + alpha, -2.0 * cs, 1.0 - alpha]),
+            BiquadKind::Bandpass => ([alpha, 0.0, -alpha], [1.0 + alpha, -2.0 * cs, 1.0 - alpha]),
+            BiquadKind::Notch => ([1.0, -2.0 * cs, 1.0], [1.0 + alpha, -2.0 * cs, 1.0 - alpha]),
+            BiquadKind::Allpass => ([1.0 - alpha, -2.0 * cs, 1.0 + alpha], [1.0 + alpha, -2.0 * cs, 1.0 - alpha]),
+            BiquadKind::Peaking => ([1.0 + alpha * a, -2.0 * cs, 1.0 - alpha * a], [1.0 + alpha / a, -2.0 * cs, 1.0 - alpha / a]),
+            BiquadKind::Lowshelf => (
+                [a * ((a + 1.0) - (a - 1.0) * cs + sa), 2.0 * a * ((a - 1.0) - (a + 1.0) * cs), a * ((a + 1.0) - (a - 1.0) * cs - sa)],
+                [(a + 1.0) + (a - 1.0) * cs + sa, -2.0 * ((a - 1.0) + (a + 1.0) * cs), (a + 1.0) + (a - 1.0) * cs - sa]),
+            BiquadKind::Highshelf => (
+                [a * ((a + 1.0) + (a - 1.0) * cs + sa), -2.0 * a * ((a - 1.0) + (a + 1.0) * cs), a * ((a + 1.0) + (a - 1.0) * cs - sa)],
+                [(a + 1.0) - (a - 1.0) * cs + sa, 2.0 * ((a - 1.0) - (a + 1.0) * cs), (a + 1.0) - (a - 1.0) * cs - sa]),
+        };
+        self.b = [b[0] / a[0], b[1] / a[0], b[2] / a[0]];
+        self.a = [a[1] / a[0], a[2] / a[0]];
+    }
+
+    pub fn tick(&mut self, x: TNT) -> TNT {
+        let x = x as f64;
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y as TNT
+    }
+}
+
+// v freq q lpf~ -> v, v freq q gain peak~ -> v
+//
+// Every parameter is a number or a vector of per-sample values, gain in dB.
+pub struct BiquadWord {
+    filter: Biquad,
+    values: [Vec<TNT>; 3],      // modulated freq, q and gain of a block
+    ov: Option<SharedVector>,
+}
+
+macro_rules! biquad_word {
+    ($fname:ident, $kind:expr) => {
+        pub fn $fname(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+            Box::new( BiquadWord {
+                filter: Biquad::new($kind, env.query("SampleRate").0),
+                values: Default::default(),
+                ov: None,
+            })
+        }
+    }
+}
+
+impl BiquadWord {
+    biquad_word!{lowpass, BiquadKind::Lowpass}
+    biquad_word!{highpass, BiquadKind::Highpass}
+    biquad_word!{bandpass, BiquadKind::Bandpass}
+    biquad_word!{notch, BiquadKind::Notch}
+    biquad_word!{allpass, BiquadKind::Allpass}
+    biquad_word!{peaking, BiquadKind::Peaking}
+    biquad_word!{lowshelf, BiquadKind::Lowshelf}
+    biquad_word!{highshelf, BiquadKind::Highshelf}
+}
+
+impl NativeWord for BiquadWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let np = if self.filter.kind.has_gain() { 3 } else { 2 };
+        let size = signal_size(stack, np, "Biquad filter");
+
+        let gain = if np == 3 { Control::pop(stack, size) } else { Control::N(0.0) };
+        let q = Control::pop(stack, size);
+        let freq = Control::pop(stack, size);
+        let x = stack.pop_vector();
+
+        let out = output(&mut self.ov, size);
+        {
+            let x = x.vec();
+            let mut y = out.vec_mut();
+            if freq.is_number() && q.is_number() && gain.is_number() {
+                self.filter.design(freq.at(0), q.at(0), gain.at(0));
+                for i in 0..size {
+                    y[i] = self.filter.tick(x[i]);
+                }
+            } else {
+                let [f, r, g] = &mut self.values;
+                freq.fill(f, size);
+                q.fill(r, size);
+                gain.fill(g, size);
+                for i in 0..size {
+                    self.filter.design(f[i], r[i], g[i]);
+                    y[i] = self.filter.tick(x[i]);
+                }
+            }
+        }
+        stack.push_vector(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use crate::TNT;
+    use crate::runtime::YjrEnviroment;
+    use crate::words::filters::{Biquad, BiquadKind};
+
+    // magnitude of the frequency response at `freq` Hz
+    fn magnitude(f: &Biquad, freq: f64) -> f64 {
+        let w = 2.0 * PI * freq / f.rate;
+        let (z1, z2) = ((-w).sin_cos(), (-2.0 * w).sin_cos());
+        let num = (f.b[0] + f.b[1] * z1.1 + f.b[2] * z2.1, f.b[1] * z1.0 + f.b[2] * z2.0);
+        let den = (1.0 + f.a[0] * z1.1 + f.a[1] * z2.1, f.a[0] * z1.0 + f.a[1] * z2.0);
+        num.0.hypot(num.1) / den.0.hypot(den.1)
+    }
+
+    fn db(x: f64) -> f64 {
+        20.0 * x.log10()
+    }
+
+    fn design(kind: BiquadKind, freq: TNT, q: TNT, gain: TNT) -> Biquad {
+        let mut f = Biquad::new(kind, 48000);
+        f.design(freq, q, gain);
+        f
+    }
+
+    #[test]
+    fn frequency_responses() {
+        let q = std::f32::consts::FRAC_1_SQRT_2;
+
+        let f = design(BiquadKind::Lowpass, 1000.0, q, 0.0);
+        assert!(db(magnitude(&f, 1.0)).abs() < 0.01);
+        assert!((db(magnitude(&f, 1000.0)) + 3.01).abs() < 0.01);
+        assert!(db(magnitude(&f, 10000.0)) < -38.0);
+
+        let f = design(BiquadKind::Highpass, 1000.0, q, 0.0);
+        assert!(db(magnitude(&f, 20000.0)).abs() < 0.01);
+        assert!((db(magnitude(&f, 1000.0)) + 3.01).abs() < 0.01);
+        assert!(db(magnitude(&f, 100.0)) < -38.0);
+
+        let f = design(BiquadKind::Bandpass, 2000.0, 2.0, 0.0);
+        assert!(db(magnitude(&f, 2000.0)).abs() < 0.01);
+        assert!(db(magnitude(&f, 200.0)) < -20.0);
+        assert!(db(magnitude(&f, 20000.0)) < -20.0);
+
+        let f = design(BiquadKind::Notch, 2000.0, 2.0, 0.0);
+        assert!(magnitude(&f, 2000.0) < 1.0e-6);
+        assert!(db(magnitude(&f, 100.0)).abs() < 0.1);
+
+        let f = design(BiquadKind::Allpass, 2000.0, 2.0, 0.0);
+        for freq in [10.0, 500.0, 2000.0, 9000.0, 23000.0] {
+            assert!((magnitude(&f, freq) - 1.0).abs() < 1.0e-9);
+        }
+
+        let f = design(BiquadKind::Peaking, 3000.0, 1.0, 6.0);
+        assert!((db(magnitude(&f, 3000.0)) - 6.0).abs() < 0.01);
+        assert!(db(magnitude(&f, 10.0)).abs() < 0.01);
+
+        let f = design(BiquadKind::Lowshelf, 300.0, q, -12.0);
+        assert!((db(magnitude(&f, 1.0)) + 12.0).abs() < 0.01);
+        assert!((db(magnitude(&f, 300.0)) + 6.0).abs() < 0.1);
+        assert!(db(magnitude(&f, 20000.0)).abs() < 0.05);
+
+        let f = design(BiquadKind::Highshelf, 5000.0, q, 9.0);
+        assert!((db(magnitude(&f, 23999.0)) - 9.0).abs() < 0.05);
+        assert!(db(magnitude(&f, 10.0)).abs() < 0.01);
+    }
+
+    // steady state amplitude of a filtered sine
+    fn measure(filter: &str, freq: TNT) -> f64 {
+        let mut env = YjrEnviroment::new(48000);
+        let txt = format!("1024 {} dsp.os.osc {}", freq, filter);
+        let mut rt = env.build(&txt);
+        for _ in 0..16 {
+            rt.stack.clear();
+            rt.run();
+        }
+        let y = rt.stack.pop().as_vector();
+        let y = y.vec();
+        (2.0 * y.iter().map(|x| (*x as f64).powi(2)).sum::<f64>() / y.size() as f64).sqrt()
+    }
+
+    #[test]
+    fn filter_words() {
+        // test tones have whole periods in a block
+        let cases = [
+            ("937.5 0.707 lpf~", BiquadKind::Lowpass, 937.5, 0.707, 0.0, 937.5),
+            ("937.5 0.707 lpf~", BiquadKind::Lowpass, 937.5, 0.707, 0.0, 93.75),
+            ("937.5 0.707 hpf~", BiquadKind::Highpass, 937.5, 0.707, 0.0, 468.75),
+            ("937.5 4 bpf~", BiquadKind::Bandpass, 937.5, 4.0, 0.0, 1875.0),
+            ("1000 2 notch~", BiquadKind::Notch, 1000.0, 2.0, 0.0, 937.5),
+            ("1000 2 apf~", BiquadKind::Allpass, 1000.0, 2.0, 0.0, 937.5),
+            ("937.5 1 6 peak~", BiquadKind::Peaking, 937.5, 1.0, 6.0, 937.5),
+            ("200 0.707 -12 lowshelf~", BiquadKind::Lowshelf, 200.0, 0.707, -12.0, 93.75),
+            ("5000 0.707 9 highshelf~", BiquadKind::Highshelf, 5000.0, 0.707, 9.0, 9375.0),
+        ];
+        for (script, kind, freq, q, gain, tone) in cases {
+            let expected = db(magnitude(&design(kind, freq, q, gain), tone as f64));
+            let got = db(measure(script, tone));
+            assert!((got - expected).abs() < 0.05, "{} at {} Hz: {} dB, expected {} dB", script, tone, got, expected);
+        }
+
+        // per-sample parameters with constant values filter the same
+        let a = measure("1000 0.707 lpf~", 3000.0);
+        let b = measure("1000 1024 ones~ * 0.707 lpf~", 3000.0);
+        let c = measure("1000 1024 ones~ * 0.707 1024 ones~ * lpf~", 3000.0);
+        assert!((a - b).abs() < 1.0e-6);
+        assert!((a - c).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn modulated_cutoff() {
+        let mut env = YjrEnviroment::new(48000);
+        let mut rt = env.build("256 ones~ 10 256 ones~ * 0.707 lpf~");
+        rt.run();
+        let y = rt.stack.pop().as_vector();
+        // step response rises slowly with a low cutoff
+        assert!(y.vec()[255] < 0.1);
+        assert!(y.vec()[255] > y.vec()[0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Modulation vector must have same length as the signal!")]
+    fn modulation_length() {
+        let mut env = YjrEnviroment::new(48000);
+        let mut rt = env.build("256 ones~ 10 64 ones~ * 0.707 lpf~");
+        rt.run();
+    }
+}
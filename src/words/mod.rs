@@ -0,0 +1,239 @@
+use crate::TNT;
+use crate::vector::Vector;
+use crate::runtime::{YjrEnviroment, YjrStack, YjrItem, SharedVector};
+
+mod probes;
+mod spectral;
+mod filters;
+mod convolution;
+mod delays;
+mod envelopes;
+mod oscillators;
+mod wavetables;
+mod noises;
+mod state;
+
+pub fn insert_native_words(env: &mut YjrEnviroment) {
+    // signal capture for debugging and tests
+    env.insert_native_word("probe~", probes::ProbeWord::new);
+
+    // spectral processing
+    env.insert_native_word("fft", spectral::FFTWord::new);
+    env.insert_native_word("ifft", spectral::IFFTWord::new);
+    env.insert_native_word("polar", spectral::Polar::new);
+    env.insert_native_word("rect", spectral::Rect::new);
+    env.insert_native_word("stft~", spectral::StftWord::new);
+    env.insert_native_word("istft~", spectral::IstftWord::new);
+
+    // RBJ biquads
+    env.insert_native_word("lpf~", filters::BiquadWord::lowpass);
+    env.insert_native_word("hpf~", filters::BiquadWord::highpass);
+    env.insert_native_word("bpf~", filters::BiquadWord::bandpass);
+    env.insert_native_word("notch~", filters::BiquadWord::notch);
+    env.insert_native_word("apf~", filters::BiquadWord::allpass);
+    env.insert_native_word("peak~", filters::BiquadWord::peaking);
+    env.insert_native_word("lowshelf~", filters::BiquadWord::lowshelf);
+    env.insert_native_word("highshelf~", filters::BiquadWord::highshelf);
+
+    // convolution
+    env.insert_native_word("conv~", convolution::ConvWord::new);
+
+    // delays and reverb
+    env.insert_native_word("delay~", delays::DelayWord::new);
+    env.insert_native_word("comb~", delays::CombWord::new);
+    env.insert_native_word("allpass~", delays::AllpassWord::new);
+    env.insert_native_word("reverb~", delays::ReverbWord::new);
+
+    // envelopes
+    env.insert_native_word("adsr~", envelopes::AdsrWord::new);
+    env.insert_native_word("adsre~", envelopes::AdsrWord::exponential);
+    env.insert_native_word("env~", envelopes::EnvWord::new);
+
+    // band-limited oscillators
+    env.insert_native_word("saw~", oscillators::OscWord::saw);
+    env.insert_native_word("square~", oscillators::OscWord::square);
+    env.insert_native_word("pulse~", oscillators::OscWord::pulse);
+    env.insert_native_word("tri~", oscillators::OscWord::triangle);
+    env.insert_native_word("sync~", oscillators::OscWord::sync);
+
+    // wavetables
+    env.insert_native_word("wavetable~", wavetables::WavetableWord::new);
+
+    // seeded noises
+    env.insert_native_word("pink~", noises::NoiseWord::pink);
+    env.insert_native_word("brown~", noises::NoiseWord::brown);
+    env.insert_native_word("gauss~", noises::NoiseWord::gauss);
+    env.insert_native_word("velvet~", noises::VelvetWord::new);
+
+    // state across blocks
+    env.insert_native_word("prev~", state::PrevWord::new);
+    env.insert_native_word("last", state::LastWord::new);
+    env.insert_native_word("accum", state::AccumWord::new);
+
+    env.insert_native_effect("probe~", "v s -- v | n s -- n");
+    env.insert_native_effect("fft", "v -- v v");
+    env.insert_native_effect("ifft", "v v -- v");
+    env.insert_native_effect("polar", "v v -- v v");
+    env.insert_native_effect("rect", "v v -- v v");
+    env.insert_native_effect("stft~", "v n n s -- v v");
+    env.insert_native_effect("istft~", "v v n n s -- v");
+    env.insert_native_effect("conv~", "v v -- v | v s -- v");
+    env.insert_native_effect("delay~", &control_effect("v", 1, "v"));
+    env.insert_native_effect("comb~", &control_effect("v", 2, "v"));
+    env.insert_native_effect("allpass~", &control_effect("v", 2, "v"));
+    env.insert_native_effect("reverb~", "v n n n -- v");
+    env.insert_native_effect("adsr~", "v n n n n -- v");
+    env.insert_native_effect("adsre~", "v n n n n -- v");
+    env.insert_native_effect("env~", "v [n] -- v");
+    for name in ["saw~", "square~", "tri~"] {
+        env.insert_native_effect(name, &control_effect("n", 1, "v"));
+    }
+    for name in ["pulse~", "sync~"] {
+        env.insert_native_effect(name, &control_effect("n", 2, "v"));
+    }
+    env.insert_native_effect("wavetable~", &format!("{} | {}", control_effect("n v", 2, "v"), control_effect("n m", 2, "v")));
+    for name in ["pink~", "brown~", "gauss~"] {
+        env.insert_native_effect(name, "n -- v");
+    }
+    env.insert_native_effect("velvet~", &control_effect("n", 1, "v"));
+    env.insert_native_effect("prev~", "v -- v | n -- n");
+    env.insert_native_effect("last", "v -- n");
+    env.insert_native_effect("accum", "v -- v | n -- n");
+    for name in ["lpf~", "hpf~", "bpf~", "notch~", "apf~"] {
+        env.insert_native_effect(name, &control_effect("v", 2, "v"));
+    }
+    for name in ["peak~", "lowshelf~", "highshelf~"] {
+        env.insert_native_effect(name, &control_effect("v", 3, "v"));
+    }
+}
+
+// all alternatives of `count` controls, each a number or a vector
+pub(crate) fn control_effect(inputs: &str, count: usize, outputs: &str) -> String {
+    (0..1 << count).map(|bits| {
+        let mut items: Vec<&str> = inputs.split_whitespace().collect();
+        items.extend( (0..count).map(|i| if bits & (1 << i) == 0 { "n" } else { "v" }) );
+        format!("{} -- {}", items.join(" "), outputs)
+    }).collect::<Vec<_>>().join(" | ")
+}
+
+// reuses the output vector of last run when size is not changed
+pub(crate) fn output(slot: &mut Option<SharedVector>, size: usize) -> SharedVector {
+    if let Some(v) = slot {
+        if v.vec().size() == size {
+            return v.clone();
+        }
+    }
+    let v = SharedVector::new( Vector::<TNT>::zeros(size) );
+    *slot = Some(v.clone());
+    v
+}
+
+/// Length of the signal vector below `params` items on the stack.
+pub(crate) fn signal_size(stack: &YjrStack, params: usize, name: &str) -> usize {
+    let n = stack.size();
+    if n <= params {
+        panic!("{} needs a signal and {} parameters", name, params);
+    }
+    match &stack.items()[n - params - 1] {
+        YjrItem::V(v) => v.vec().size(),
+        _ => panic!("{} can only process a vector!", name),
+    }
+}
+
+/// A parameter given as a number, or as a vector for per-sample modulation.
+pub(crate) enum Control {
+    N(TNT),
+    V(SharedVector),
+}
+
+impl Control {
+    /// Pops a control for a block of `size` samples.
+    pub fn pop(stack: &mut YjrStack, size: usize) -> Control {
+        match stack.pop() {
+            YjrItem::N(n) => Control::N(n),
+            YjrItem::V(v) => {
+                if v.vec().size() != size {
+                    panic!("Modulation vector must have same length as the signal!");
+                }
+                Control::V(v)
+            },
+            _ => panic!("Control must be a number or a vector!"),
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Control::N(_))
+    }
+
+    /// Value at sample `i` of the block.
+    pub fn at(&self, i: usize) -> TNT {
+        match self {
+            Control::N(n) => *n,
+            Control::V(v) => v.vec()[i],
+        }
+    }
+
+    /// Values of a block written into `buf`, reusing its storage.
+    pub fn fill(&self, buf: &mut Vec<TNT>, size: usize) {
+        buf.clear();
+        match self {
+            Control::N(n) => buf.resize(size, *n),
+            Control::V(v) => buf.extend_from_slice( v.vec().data() ),
+        }
+    }
+
+    pub fn values(&self, size: usize) -> Vec<TNT> {
+        match self {
+            Control::N(n) => vec![*n; size],
+            Control::V(v) => v.vec().data().clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use crate::TNT;
+    use crate::runtime::{YjrEnviroment, NativeWord, YjrStack};
+
+    /// Runs a word in blocks of `block` samples until `length`, `push` puts
+    /// the inputs of the block starting at the given sample on the stack.
+    pub(crate) fn drive(word: fn(&YjrEnviroment) -> Box<dyn NativeWord>, rate: i32, block: usize, length: usize,
+                        push: &dyn Fn(&mut YjrStack, usize, usize)) -> Vec<TNT> {
+        let env = YjrEnviroment::new(rate);
+        let mut w = word(&env);
+        let mut stack = YjrStack::new();
+        let mut y = Vec::new();
+        while y.len() < length {
+            push(&mut stack, y.len(), block);
+            w.run(&mut stack);
+            y.extend_from_slice( stack.pop_vector().vec().data() );
+        }
+        y.truncate(length);
+        y
+    }
+
+    /// Top vector of a script run `blocks` times, joined.
+    pub(crate) fn render(rate: i32, script: &str, blocks: usize) -> Vec<TNT> {
+        let mut env = YjrEnviroment::new(rate);
+        let mut rt = env.build(script);
+        let mut y = Vec::new();
+        for _ in 0..blocks {
+            rt.run();
+            y.extend_from_slice( rt.stack.pop().as_vector().vec().data() );
+        }
+        y
+    }
+
+    /// Every vector left on the stack by a script, block by block.
+    pub(crate) fn frames(rate: i32, script: &str, blocks: usize) -> Vec<Vec<Vec<TNT>>> {
+        let mut env = YjrEnviroment::new(rate);
+        let mut rt = env.build(script);
+        let mut result = Vec::new();
+        for _ in 0..blocks {
+            rt.run();
+            result.push( rt.stack.items().iter().map(|i| i.clone().as_vector().vec().data().clone()).collect() );
+            rt.stack.clear();
+        }
+        result
+    }
+}
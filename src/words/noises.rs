@@ -0,0 +1,291 @@
+use crate::TNT;
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, SharedVector};
+use crate::words::{output, Control};
+
+/// xorshift64* generator.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // zero is the only invalid state
+        Rng(if seed == 0 { 0x9e3779b97f4a7c15 } else { seed })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    /// Uniform in 0..1.
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in -1..1.
+    pub fn white(&mut self) -> f64 {
+        2.0 * self.uniform() - 1.0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Color {
+    Pink,
+    Brown,
+    Gauss,
+}
+
+// n pink~ -> v, n brown~ -> v, n gauss~ -> v
+//
+// Pink is Paul Kellet's refined filter of white noise, brown a leaky
+// integrator, gauss has unit variance.
+pub struct NoiseWord {
+    color: Color,
+    rng: Rng,
+    state: [f64; 7],
+    spare: Option<f64>,     // second value of Box-Muller
+    ov: Option<SharedVector>,
+}
+
+macro_rules! noise_word {
+    ($fname:ident, $color:expr) => {
+        pub fn $fname(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+            Box::new( NoiseWord {
+                color: $color,
+                rng: Rng::new( env.seed() ),
+                state: [0.0; 7],
+                spare: None,
+                ov: None,
+            })
+        }
+    }
+}
+
+impl NoiseWord {
+    noise_word!{pink, Color::Pink}
+    noise_word!{brown, Color::Brown}
+    noise_word!{gauss, Color::Gauss}
+
+    fn tick(&mut self) -> f64 {
+        match self.color {
+            Color::Pink => {
+                let w = self.rng.white();
+                let b = &mut self.state;
+                b[0] = 0.99886 * b[0] + w * 0.0555179;
+                b[1] = 0.99332 * b[1] + w * 0.0750759;
+                b[2] = 0.96900 * b[2] + w * 0.1538520;
+                b[3] = 0.86650 * b[3] + w * 0.3104856;
+                b[4] = 0.55000 * b[4] + w * 0.5329522;
+                b[5] = -0.7616 * b[5] - w * 0.0168980;
+                let y = b.iter().sum::<f64>() + w * 0.5362;
+                b[6] = w * 0.115926;
+                y * 0.11
+            },
+            Color::Brown => {
+                let w = self.rng.white();
+                self.state[0] = (self.state[0] + 0.02 * w) / 1.02;
+                self.state[0] * 3.5
+            },
+            Color::Gauss => {
+                if let Some(y) = self.spare.take() {
+                    return y;
+                }
+                let u = 1.0 - self.rng.uniform();
+                let v = self.rng.uniform();
+                let r = (-2.0 * u.ln()).sqrt();
+                let a = 2.0 * std::f64::consts::PI * v;
+                self.spare = Some(r * a.sin());
+                r * a.cos()
+            },
+        }
+    }
+}
+
+impl NativeWord for NoiseWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let count = stack.pop_number() as usize;
+        let out = output(&mut self.ov, count);
+        {
+            let mut y = out.vec_mut();
+            for i in 0..count {
+                y[i] = self.tick() as TNT;
+            }
+        }
+        stack.push_vector(out);
+    }
+}
+
+// n density velvet~ -> v
+//
+// Velvet noise, one impulse of random sign at a random place in every
+// period of 1 / density seconds.
+pub struct VelvetWord {
+    rate: f64,
+    rng: Rng,
+    phase: f64,
+    at: f64,
+    sign: TNT,
+    pending: bool,
+    ov: Option<SharedVector>,
+}
+impl VelvetWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        let mut w = VelvetWord {
+            rate: env.query("SampleRate").0 as f64,
+            rng: Rng::new( env.seed() ),
+            phase: 0.0,
+            at: 0.0,
+            sign: 1.0,
+            pending: false,
+            ov: None,
+        };
+        w.next_period();
+        Box::new(w)
+    }
+
+    fn next_period(&mut self) {
+        self.at = self.rng.uniform();
+        self.sign = if self.rng.uniform() < 0.5 { -1.0 } else { 1.0 };
+        self.pending = true;
+    }
+}
+impl NativeWord for VelvetWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let n = stack.size();
+        if n < 2 {
+            panic!("velvet~ needs a length and a density");
+        }
+        let count = match &stack.items()[n - 2] {
+            YjrItem::N(c) if c.fract() == 0.0 && *c >= 0.0 => *c as usize,
+            _ => panic!("Noise length must be a integer!"),
+        };
+        let density = Control::pop(stack, count).values(count);
+        stack.pop_number();
+
+        let out = output(&mut self.ov, count);
+        {
+            let mut y = out.vec_mut();
+            for i in 0..count {
+                let dt = (density[i] as f64 / self.rate).clamp(0.0, 1.0);
+                // the impulse is not skipped when the period ends early
+                y[i] = 0.0;
+                if self.pending && (self.phase >= self.at || self.phase + dt >= 1.0) {
+                    y[i] = self.sign;
+                    self.pending = false;
+                }
+                self.phase += dt;
+                if self.phase >= 1.0 {
+                    self.phase = self.phase.fract();
+                    self.next_period();
+                }
+            }
+        }
+        stack.push_vector(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::vector::fft::{FFT, Complex};
+    use crate::runtime::YjrEnviroment;
+
+    const RATE: i32 = 44100;
+
+    // runs a script leaving two vectors, `blocks` times
+    fn render2(script: &str, blocks: usize) -> (Vec<TNT>, Vec<TNT>) {
+        let mut env = YjrEnviroment::new(RATE);
+        let mut rt = env.build(script);
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        for _ in 0..blocks {
+            rt.run();
+            b.extend_from_slice( rt.stack.pop().as_vector().vec().data() );
+            a.extend_from_slice( rt.stack.pop().as_vector().vec().data() );
+        }
+        (a, b)
+    }
+
+    fn correlation(a: &[TNT], b: &[TNT]) -> f64 {
+        let mean = |x: &[TNT]| x.iter().map(|v| *v as f64).sum::<f64>() / x.len() as f64;
+        let (ma, mb) = (mean(a), mean(b));
+        let (mut ab, mut aa, mut bb) = (0.0, 0.0, 0.0);
+        for (x, y) in a.iter().zip(b.iter()) {
+            let (x, y) = (*x as f64 - ma, *y as f64 - mb);
+            ab += x * y;
+            aa += x * x;
+            bb += y * y;
+        }
+        ab / (aa * bb).sqrt()
+    }
+
+    // averaged power spectrum in blocks of 1024, slope in dB per octave
+    // from 500 Hz to 4000 Hz
+    fn slope(x: &[TNT]) -> f64 {
+        let n = 1024;
+        let mut plan = FFT::new(n);
+        let mut power = vec![0.0; n / 2];
+        for block in x.chunks_exact(n) {
+            let mut s: Vec<Complex<f64>> = block.iter().map(|v| Complex::new(*v as f64, 0.0)).collect();
+            plan.forward(&mut s);
+            for (p, c) in power.iter_mut().zip(s.iter()) {
+                *p += c.norm_sqr();
+            }
+        }
+        let band = |lo: f64| {
+            let k = |f: f64| (f * n as f64 / RATE as f64).round() as usize;
+            let b = &power[k(lo)..k(lo * 2.0)];
+            10.0 * (b.iter().sum::<f64>() / b.len() as f64).log10()
+        };
+        (band(2000.0) - band(500.0)) / 2.0
+    }
+
+    #[test]
+    fn colors() {
+        let (pink, brown) = render2("1024 pink~ 1024 brown~", 64);
+        let s = slope(&pink);
+        assert!((s + 3.0).abs() < 0.7, "pink slope {:.2} dB", s);
+        let s = slope(&brown);
+        assert!((s + 6.0).abs() < 0.7, "brown slope {:.2} dB", s);
+        assert!(pink.iter().chain(brown.iter()).all(|v| v.abs() < 1.5));
+
+        let (white, gauss) = render2("1024 dsp.no.noise 1024 gauss~", 64);
+        let s = slope(&white);
+        assert!(s.abs() < 0.7, "white slope {:.2} dB", s);
+        let mean = gauss.iter().map(|v| *v as f64).sum::<f64>() / gauss.len() as f64;
+        let var = gauss.iter().map(|v| (*v as f64 - mean).powi(2)).sum::<f64>() / gauss.len() as f64;
+        assert!(mean.abs() < 0.03 && (var - 1.0).abs() < 0.03);
+    }
+
+    #[test]
+    fn decorrelation() {
+        for word in ["dsp.no.noise", "pink~", "brown~", "gauss~", "2000 velvet~"] {
+            let (a, b) = render2(&format!("1024 {} 1024 {}", word, word), 64);
+            let r = correlation(&a, &b);
+            assert!(r.abs() < 0.1, "{}: correlation {:.3}", word, r);
+        }
+
+        // same seed and script, same noise
+        let a = render2("1024 pink~ 1024 gauss~", 2);
+        let b = render2("1024 pink~ 1024 gauss~", 2);
+        assert_eq!(a, b);
+
+        // other global seed
+        let mut env = YjrEnviroment::new(RATE);
+        env.config("Seed", (7, 0.0, false));
+        let mut rt = env.build("1024 pink~");
+        rt.run();
+        assert_ne!(rt.stack.pop().as_vector().vec().data(), &a.0[..1024].to_vec());
+    }
+
+    #[test]
+    fn velvet() {
+        let (a, _) = render2("1024 441 velvet~ 1024 zeros~", 10);
+        assert!(a.iter().all(|v| *v == 0.0 || v.abs() == 1.0));
+        // one impulse in every 100 samples
+        for period in a.chunks_exact(100) {
+            assert_eq!(period.iter().filter(|v| **v != 0.0).count(), 1);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use crate::TNT;
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, SharedVector};
+use crate::words::{output, Control};
+
+// residual of a band-limited unit step falling by 2 at phase 0, `t` is
+// the phase and `dt` the phase increment
+fn poly_blep(t: f64, dt: f64) -> f64 {
+    if t < dt {
+        let x = t / dt;
+        x + x - x * x - 1.0
+    } else if t > 1.0 - dt {
+        let x = (t - 1.0) / dt;
+        x * x + x + x + 1.0
+    } else {
+        0.0
+    }
+}
+
+// residual of a band-limited unit corner, the integral of poly_blep
+fn poly_blamp(t: f64, dt: f64) -> f64 {
+    if t < dt {
+        let x = t / dt - 1.0;
+        -x * x * x / 3.0
+    } else if t > 1.0 - dt {
+        let x = (t - 1.0) / dt + 1.0;
+        x * x * x / 3.0
+    } else {
+        0.0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Waveform {
+    Saw,
+    Square,
+    Pulse,
+    Triangle,
+    Sync,
+}
+
+/// PolyBLEP oscillator state, waveforms are in -1..1.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlepOsc {
+    wave: Waveform,
+    rate: f64,
+    phase: f64,
+    master: f64,        // phase of the sync master
+    pending: f64,       // sync~ is delayed one sample for the step before reset
+}
+
+impl BlepOsc {
+    pub fn new(wave: Waveform, rate: i32) -> Self {
+        BlepOsc {
+            wave,
+            rate: rate as f64,
+            phase: 0.0,
+            master: 0.0,
+            pending: 0.0,
+        }
+    }
+
+    /// Next sample at `freq`, `p` is the pulse width or the sync master
+    /// frequency.
+    pub fn tick(&mut self, freq: TNT, p: TNT) -> TNT {
+        let dt = (freq as f64 / self.rate).abs().min(0.5);
+        let t = self.phase;
+        let y = match self.wave {
+            Waveform::Saw => 2.0 * t - 1.0 - poly_blep(t, dt),
+            Waveform::Square | Waveform::Pulse => {
+                let w = if self.wave == Waveform::Square { 0.5 } else { (p as f64).clamp(0.0, 1.0) };
+                let naive = if t < w { 1.0 } else { -1.0 };
+                naive + poly_blep(t, dt) - poly_blep((t - w + 1.0).fract(), dt)
+            },
+            Waveform::Triangle => {
+                let naive = 1.0 - 4.0 * (t - 0.5).abs();
+                naive + 4.0 * dt * (poly_blamp(t, dt) - poly_blamp((t + 0.5).fract(), dt))
+            },
+            Waveform::Sync => return self.sync(dt, p),
+        };
+        self.phase = (t + dt).fract();
+        y as TNT
+    }
+
+    fn sync(&mut self, dt: f64, master: TNT) -> TNT {
+        let dm = (master as f64 / self.rate).abs().min(0.5);
+        self.master += dm;
+        self.phase += dt;
+
+        self.phase = self.phase.fract();
+        let current = if self.master >= 1.0 {
+            // reset happened `frac` samples ago, the step replaces the
+            // one of a wrap at phase 0
+            self.master -= 1.0;
+            let frac = if dm > 0.0 { self.master / dm } else { 0.0 };
+            let at = (self.phase - frac * dt).rem_euclid(1.0);
+            let h = -2.0 * at;
+            self.pending += h / 2.0 * frac * frac;
+            self.phase = frac * dt;
+            2.0 * self.phase - 1.0 - h / 2.0 * (1.0 - frac) * (1.0 - frac)
+        } else {
+            2.0 * self.phase - 1.0 - poly_blep(self.phase, dt)
+        };
+
+        let y = self.pending;
+        self.pending = current;
+        y as TNT
+    }
+}
+
+// n freq saw~ -> v, n freq width pulse~ -> v, n freq master sync~ -> v
+//
+// Frequencies and width are numbers or per-sample vectors.
+pub struct OscWord {
+    osc: BlepOsc,
+    ov: Option<SharedVector>,
+}
+
+macro_rules! osc_word {
+    ($fname:ident, $wave:expr) => {
+        pub fn $fname(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+            Box::new( OscWord {
+                osc: BlepOsc::new($wave, env.query("SampleRate").0),
+                ov: None,
+            })
+        }
+    }
+}
+
+impl OscWord {
+    osc_word!{saw, Waveform::Saw}
+    osc_word!{square, Waveform::Square}
+    osc_word!{pulse, Waveform::Pulse}
+    osc_word!{triangle, Waveform::Triangle}
+    osc_word!{sync, Waveform::Sync}
+}
+
+impl NativeWord for OscWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let np = match self.osc.wave {
+            Waveform::Pulse | Waveform::Sync => 2,
+            _ => 1,
+        };
+        let n = stack.size();
+        if n <= np {
+            panic!("Oscillator needs a length and {} parameters", np);
+        }
+        let count = match &stack.items()[n - np - 1] {
+            YjrItem::N(c) if c.fract() == 0.0 && *c >= 0.0 => *c as usize,
+            _ => panic!("Oscillator length must be a integer!"),
+        };
+
+        let p = if np == 2 { Control::pop(stack, count) } else { Control::N(0.0) };
+        let freq = Control::pop(stack, count);
+        stack.pop_number();
+
+        let (freq, p) = (freq.values(count), p.values(count));
+        let out = output(&mut self.ov, count);
+        {
+            let mut y = out.vec_mut();
+            for i in 0..count {
+                y[i] = self.osc.tick(freq[i], p[i]);
+            }
+        }
+        stack.push_vector(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::vector::fft::{FFT, Complex};
+    use crate::runtime::YjrEnviroment;
+    use crate::words::testing;
+
+    const N: usize = 8192;
+    const RATE: f64 = 44100.0;
+    // cycles in N samples, coprime with N so aliases never hit harmonics
+    const CYCLES: usize = 1001;
+
+    fn render(script: &str) -> Vec<TNT> {
+        let y = testing::render(RATE as i32, script, 2 * N / 1024);
+        // skip the start for sync~ delay and transients
+        y[N..].to_vec()
+    }
+
+    type Wave<'a> = &'a dyn Fn(f64) -> f64;
+
+    fn naive(f: Wave) -> Vec<TNT> {
+        let dt = CYCLES as f64 / N as f64;
+        (0..N).map(|i| f((i as f64 * dt).fract()) as TNT).collect()
+    }
+
+    // energy off the harmonic bins below Nyquist relative to all, in dB
+    fn aliasing(x: &[TNT]) -> f64 {
+        let mut buf: Vec<Complex<f64>> = x.iter().map(|v| Complex::new(*v as f64, 0.0)).collect();
+        FFT::new(N).forward(&mut buf);
+        let mut total = 0.0;
+        let mut alias = 0.0;
+        for (k, c) in buf.iter().enumerate().take(N / 2).skip(1) {
+            let e = c.norm_sqr();
+            total += e;
+            if k % CYCLES != 0 {
+                alias += e;
+            }
+        }
+        10.0 * (alias / total).log10()
+    }
+
+    #[test]
+    fn band_limited() {
+        let freq = CYCLES as f64 * RATE / N as f64;
+        let cases: [(&str, Wave, f64); 4] = [
+            ("saw~", &|t| 2.0 * t - 1.0, -20.0),
+            ("square~", &|t| if t < 0.5 { 1.0 } else { -1.0 }, -20.0),
+            ("0.3 pulse~", &|t| if t < 0.3 { 1.0 } else { -1.0 }, -20.0),
+            ("tri~", &|t| 1.0 - 4.0 * (t - 0.5).abs(), -35.0),
+        ];
+        for (word, f, threshold) in cases {
+            let y = render(&format!("1024 {} {}", freq, word));
+            assert!(y.iter().all(|v| v.abs() <= 1.1));
+            let a = aliasing(&y);
+            let b = aliasing(&naive(f));
+            assert!(a < b - 12.0 && a < threshold, "{}: {:.1} dB aliasing, naive {:.1} dB", word, a, b);
+        }
+    }
+
+    #[test]
+    fn hard_sync() {
+        // the slave frequency is irrational to the master
+        let master = CYCLES as f64 * RATE / N as f64;
+        let y = render(&format!("1024 {} {} sync~", master * 1.618, master));
+        let z = naive(&|t| 2.0 * (t * 1.618).fract() - 1.0);
+        let a = aliasing(&y);
+        let b = aliasing(&z);
+        assert!(a < b - 12.0 && a < -18.0, "sync~: {:.1} dB aliasing, naive {:.1} dB", a, b);
+    }
+
+    #[test]
+    fn phase_continuity() {
+        // a low saw rises by the same step everywhere, also across blocks
+        let mut env = YjrEnviroment::new(1000);
+        let mut rt = env.build("64 10 saw~");
+        rt.run();
+        let a = rt.stack.pop().as_vector().vec().data().clone();
+        rt.run();
+        let b = rt.stack.pop().as_vector().vec().data().clone();
+        assert!((a[11] - a[10] - 0.02).abs() < 1.0e-6);
+        assert!((b[0] - a[63] - 0.02).abs() < 1.0e-6);
+
+        // per-sample frequency
+        let mut rt = env.build("4 250 0 4 ones~ * + square~");
+        rt.run();
+        assert_eq!(rt.stack.size(), 1);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, Probe};
+
+// v $name probe~ -> v
+pub struct ProbeWord {
+    length: usize,
+    rate: i32,
+    probe: Option<Probe>,
+}
+impl ProbeWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( ProbeWord {
+            length: env.query("ProbeLength").0 as usize,
+            rate: env.query("SampleRate").0,
+            probe: None,
+        })
+    }
+}
+
+impl NativeWord for ProbeWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let name = stack.pop_string();
+        if self.probe.is_none() {
+            self.probe = Some( Probe {
+                name: name.trim_start_matches(['$', '%']).to_string(),
+                rate: self.rate,
+                data: Vec::with_capacity(self.length),
+            });
+        }
+
+        let p = self.probe.as_mut().unwrap();
+        let room = self.length - p.data.len();
+        match stack.top() {
+            YjrItem::V(v) => {
+                let v = v.vec();
+                let n = room.min(v.size());
+                p.data.extend_from_slice( &v.data()[..n] );
+            },
+            YjrItem::N(n) => {
+                if room > 0 {
+                    p.data.push(*n);
+                }
+            },
+            _ => panic!("probe~ can only capture vector or number"),
+        }
+    }
+
+    fn probe(&self) -> Option<&Probe> {
+        self.probe.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::{YjrEnviroment, YjrItem};
+
+    #[test]
+    fn probe_signal() {
+        let mut env = YjrEnviroment::new(44100);
+        env.config("ProbeLength", (100, 0.0, false));
+        let txt = "64 440 dsp.os.osc $osc probe~
+                   0.5 swap * $gain probe~
+                   sum $level probe~";
+        let mut rt = env.build(txt);
+        rt.run();
+        rt.stack.clear();
+        rt.run();
+
+        assert_eq!(rt.stack.size(), 1);
+        assert!(rt.stack.top().is_number());
+        assert_eq!(rt.probes().len(), 3);
+
+        let osc = rt.probe("osc").unwrap();
+        let gain = rt.probe("gain").unwrap();
+        assert_eq!(osc.rate, 44100);
+        assert_eq!(osc.data.len(), 100);
+        assert_eq!(gain.data.len(), 100);
+        for i in 0..100 {
+            assert_eq!(osc.data[i] * 0.5, gain.data[i]);
+        }
+        assert_eq!(rt.probe("level").unwrap().data.len(), 2);
+        assert_eq!(*rt.stack.top(), YjrItem::N(rt.probe("level").unwrap().data[1]));
+        assert!(rt.probe("none").is_none());
+    }
+
+    #[test]
+    fn dump_probes() {
+        let mut env = YjrEnviroment::new(44100);
+        let mut rt = env.build("16 ones~ $one probe~");
+        rt.run();
+
+        let dir = std::env::temp_dir().join("yjriver_dump_probes");
+        rt.dump_probes(&dir).unwrap();
+
+        let wav = std::fs::read( dir.join("one.wav") ).unwrap();
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(wav.len(), 58 + 16 * 4);
+        assert_eq!(&wav[wav.len()-4..], &1.0f32.to_le_bytes());
+
+        let csv = std::fs::read_to_string( dir.join("one.csv") ).unwrap();
+        assert_eq!(csv.lines().count(), 17);
+        assert_eq!(csv.lines().nth(1).unwrap(), "0,1");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
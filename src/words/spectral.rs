@@ -0,0 +1,348 @@
+use crate::TNT;
+use crate::vector::fft::{FFT, Complex, Window};
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, SharedVector};
+use crate::words::output;
+
+fn plan(slot: &mut Option<FFT<TNT>>, size: usize) -> &mut FFT<TNT> {
+    if slot.as_ref().map(|p| p.size()) != Some(size) {
+        *slot = Some( FFT::new(size) );
+    }
+    slot.as_mut().unwrap()
+}
+
+// work buffer of a frame, only allocated when the size changes
+fn frame(buf: &mut Vec<Complex<TNT>>, size: usize) -> &mut [Complex<TNT>] {
+    buf.resize(size, Complex::new(0.0, 0.0));
+    buf
+}
+
+fn pop_window(stack: &mut YjrStack) -> Window {
+    let name = stack.pop_string();
+    match Window::from_name( name.trim_start_matches(['$', '%']) ) {
+        Some(w) => w,
+        None => panic!("Unknown window '{}', must be rect, hann, hamming or blackman", name),
+    }
+}
+
+fn pop_size(stack: &mut YjrStack) -> usize {
+    let n = stack.pop_number();
+    if n.fract() != 0.0 || n < 1.0 {
+        panic!("Size of spectral frame must be a positive integer!");
+    }
+    n as usize
+}
+
+// v fft -> re im
+pub struct FFTWord {
+    plan: Option<FFT<TNT>>,
+    buf: Vec<Complex<TNT>>,
+    re: Option<SharedVector>,
+    im: Option<SharedVector>,
+}
+impl FFTWord {
+    pub fn new(_env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( FFTWord {
+            plan: None,
+            buf: Vec::new(),
+            re: None,
+            im: None,
+        })
+    }
+}
+impl NativeWord for FFTWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let x = stack.pop_vector();
+        let buf = frame(&mut self.buf, x.vec().size());
+        for (b, v) in buf.iter_mut().zip(x.vec().iter()) {
+            *b = Complex::new(*v, 0.0);
+        }
+        plan(&mut self.plan, buf.len()).forward(buf);
+
+        let re = output(&mut self.re, buf.len());
+        let im = output(&mut self.im, buf.len());
+        for (i, c) in buf.iter().enumerate() {
+            re.vec_mut()[i] = c.re;
+            im.vec_mut()[i] = c.im;
+        }
+        stack.push_vector(re);
+        stack.push_vector(im);
+    }
+}
+
+// re im ifft -> v, the real part of inverse transform
+pub struct IFFTWord {
+    plan: Option<FFT<TNT>>,
+    buf: Vec<Complex<TNT>>,
+    out: Option<SharedVector>,
+}
+impl IFFTWord {
+    pub fn new(_env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( IFFTWord {
+            plan: None,
+            buf: Vec::new(),
+            out: None,
+        })
+    }
+}
+impl NativeWord for IFFTWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let im = stack.pop_vector();
+        let re = stack.pop_vector();
+        let buf = {
+            let (re, im) = (re.vec(), im.vec());
+            if re.size() != im.size() {
+                panic!("ifft needs real and imaginary parts of same size!");
+            }
+            let buf = frame(&mut self.buf, re.size());
+            for (b, (r, i)) in buf.iter_mut().zip(re.iter().zip(im.iter())) {
+                *b = Complex::new(*r, *i);
+            }
+            buf
+        };
+        plan(&mut self.plan, buf.len()).inverse(buf);
+
+        let out = output(&mut self.out, buf.len());
+        for (i, c) in buf.iter().enumerate() {
+            out.vec_mut()[i] = c.re;
+        }
+        stack.push_vector(out);
+    }
+}
+
+// re im polar -> mag phase, mag phase rect -> re im
+macro_rules! coordinate_word {
+    ($name:ident, $f:expr) => {
+        pub struct $name {
+            a: Option<SharedVector>,
+            b: Option<SharedVector>,
+        }
+        impl $name {
+            pub fn new(_env: &YjrEnviroment) -> Box<dyn NativeWord> {
+                Box::new( $name {
+                    a: None,
+                    b: None,
+                })
+            }
+        }
+        impl NativeWord for $name {
+            fn run(&mut self, stack: &mut YjrStack) {
+                let y = stack.pop_vector();
+                let x = stack.pop_vector();
+                let size = x.vec().size();
+                if y.vec().size() != size {
+                    panic!("Coordinate conversion needs vectors of same size!");
+                }
+                let a = output(&mut self.a, size);
+                let b = output(&mut self.b, size);
+                for i in 0..size {
+                    let (u, v) = $f(x.vec()[i], y.vec()[i]);
+                    a.vec_mut()[i] = u;
+                    b.vec_mut()[i] = v;
+                }
+                stack.push_vector(a);
+                stack.push_vector(b);
+            }
+        }
+    }
+}
+
+coordinate_word!{Polar, |re: TNT, im: TNT| (re.hypot(im), im.atan2(re))}
+coordinate_word!{Rect, |mag: TNT, phase: TNT| (mag * phase.cos(), mag * phase.sin())}
+
+// v size hop $window stft~ -> re im
+//
+// The block length must be a multiple of hop, every hop of new samples
+// makes a frame of the last `size` samples. The frames of one block are
+// concatenated in the outputs.
+pub struct StftWord {
+    plan: Option<FFT<TNT>>,
+    buf: Vec<Complex<TNT>>,
+    kind: Option<Window>,
+    window: Vec<TNT>,
+    history: Vec<TNT>,
+    re: Option<SharedVector>,
+    im: Option<SharedVector>,
+}
+impl StftWord {
+    pub fn new(_env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( StftWord {
+            plan: None,
+            buf: Vec::new(),
+            kind: None,
+            window: Vec::new(),
+            history: Vec::new(),
+            re: None,
+            im: None,
+        })
+    }
+}
+impl NativeWord for StftWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let window = pop_window(stack);
+        let hop = pop_size(stack);
+        let size = pop_size(stack);
+        let x = stack.pop_vector();
+        let x = x.vec();
+        if hop > size || !x.size().is_multiple_of(hop) {
+            panic!("stft~ needs hop not larger than frame size and block length a multiple of hop!");
+        }
+
+        if self.kind != Some(window) || self.window.len() != size {
+            self.kind = Some(window);
+            self.window = window.coefficients(size);
+            self.history = vec![0.0; size];
+        }
+
+        let frames = x.size() / hop;
+        let re = output(&mut self.re, frames * size);
+        let im = output(&mut self.im, frames * size);
+        let plan = plan(&mut self.plan, size);
+        let buf = frame(&mut self.buf, size);
+        for f in 0..frames {
+            self.history.drain(..hop);
+            self.history.extend_from_slice( &x.data()[f*hop..(f+1)*hop] );
+
+            for (b, (h, w)) in buf.iter_mut().zip(self.history.iter().zip(self.window.iter())) {
+                *b = Complex::new(h * w, 0.0);
+            }
+            plan.forward(buf);
+
+            let (mut re, mut im) = (re.vec_mut(), im.vec_mut());
+            for (i, c) in buf.iter().enumerate() {
+                re[f*size + i] = c.re;
+                im[f*size + i] = c.im;
+            }
+        }
+        drop(x);
+        stack.push_vector(re);
+        stack.push_vector(im);
+    }
+}
+
+// re im size hop $window istft~ -> v
+//
+// Overlap-add of windowed frames, normalized by the overlapped squared
+// window. Without processing the output is the input of stft~ delayed by
+// size - hop samples.
+pub struct IstftWord {
+    plan: Option<FFT<TNT>>,
+    buf: Vec<Complex<TNT>>,
+    kind: Option<Window>,
+    window: Vec<TNT>,
+    norm: Vec<TNT>,
+    acc: Vec<TNT>,
+    out: Option<SharedVector>,
+}
+impl IstftWord {
+    pub fn new(_env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( IstftWord {
+            plan: None,
+            buf: Vec::new(),
+            kind: None,
+            window: Vec::new(),
+            norm: Vec::new(),
+            acc: Vec::new(),
+            out: None,
+        })
+    }
+}
+impl NativeWord for IstftWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let window = pop_window(stack);
+        let hop = pop_size(stack);
+        let size = pop_size(stack);
+        let im = stack.pop_vector();
+        let re = stack.pop_vector();
+        let (re, im) = (re.vec(), im.vec());
+        if hop > size || re.size() % size != 0 || re.size() != im.size() {
+            panic!("istft~ needs hop not larger than frame size and whole frames of same size!");
+        }
+
+        if self.kind != Some(window) || self.window.len() != size || self.norm.len() != hop {
+            self.kind = Some(window);
+            self.window = window.coefficients(size);
+            self.norm = (0..hop).map(|i| {
+                let s: TNT = self.window.iter().skip(i).step_by(hop).map(|w| w * w).sum();
+                if s > 1.0e-6 { 1.0 / s } else { 0.0 }
+            }).collect();
+            self.acc = vec![0.0; size];
+        }
+
+        let frames = re.size() / size;
+        let out = output(&mut self.out, frames * hop);
+        let plan = plan(&mut self.plan, size);
+        let buf = frame(&mut self.buf, size);
+        for f in 0..frames {
+            for (i, b) in buf.iter_mut().enumerate() {
+                *b = Complex::new(re[f*size + i], im[f*size + i]);
+            }
+            plan.inverse(buf);
+            for (a, (b, w)) in self.acc.iter_mut().zip(buf.iter().zip(self.window.iter())) {
+                *a += b.re * w;
+            }
+
+            let mut out = out.vec_mut();
+            for i in 0..hop {
+                out[f*hop + i] = self.acc[i] * self.norm[i];
+            }
+            self.acc.drain(..hop);
+            self.acc.resize(size, 0.0);
+        }
+        stack.push_vector(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use crate::TNT;
+    use crate::words::testing::frames;
+
+    fn run(txt: &str, blocks: usize) -> Vec<Vec<Vec<TNT>>> {
+        frames(44100, txt, blocks)
+    }
+
+    #[test]
+    fn fft_words() {
+        let r = run("48 440 dsp.os.osc dup fft", 1);
+        let (x, re, im) = (&r[0][0], &r[0][1], &r[0][2]);
+        assert_eq!(re.len(), 48);
+
+        // naive DFT
+        for k in 0..48 {
+            let (mut sr, mut si) = (0.0f64, 0.0f64);
+            for (j, v) in x.iter().enumerate() {
+                let a = -2.0 * PI * ((j * k) % 48) as f64 / 48.0;
+                sr += *v as f64 * a.cos();
+                si += *v as f64 * a.sin();
+            }
+            assert!((re[k] as f64 - sr).abs() < 1e-4);
+            assert!((im[k] as f64 - si).abs() < 1e-4);
+        }
+
+        let r = run("64 440 dsp.os.osc dup fft polar rect ifft", 1);
+        for (x, y) in r[0][0].iter().zip(r[0][1].iter()) {
+            assert!((x - y).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn stft_reconstruction() {
+        for (size, hop, window) in [(256, 64, "hann"), (128, 32, "blackman"), (64, 64, "rect"), (96, 32, "hamming")] {
+            let txt = format!("64 440 dsp.os.osc dup {} {} ${} stft~ {} {} ${} istft~", size, hop, window, size, hop, window);
+            let r = run(&txt, 12);
+            let x: Vec<TNT> = r.iter().flat_map(|b| b[0].clone()).collect();
+            let y: Vec<TNT> = r.iter().flat_map(|b| b[1].clone()).collect();
+
+            let delay = size - hop;
+            for i in delay..x.len() {
+                assert!((y[i] - x[i - delay]).abs() < 1e-4, "{} {} at {}", size, window, i);
+            }
+        }
+
+        // two frames each block
+        let r = run("64 ones~ 64 32 $hann stft~", 1);
+        assert_eq!(r[0][0].len(), 128);
+        assert_eq!(r[0][1].len(), 128);
+    }
+}
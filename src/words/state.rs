@@ -0,0 +1,162 @@
+use crate::TNT;
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, SharedVector};
+use crate::words::output;
+
+// v prev~ -> v, n prev~ -> n
+//
+// One block delay, pushes the item taken at the last run. The first block
+// and a block after a change of length are silent.
+pub struct PrevWord {
+    number: TNT,
+    held: Vec<TNT>,         // copy of the last input, swapped with output
+    ov: Option<SharedVector>,
+}
+impl PrevWord {
+    pub fn new(_env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( PrevWord {
+            number: 0.0,
+            held: Vec::new(),
+            ov: None,
+        })
+    }
+}
+impl NativeWord for PrevWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        match stack.pop() {
+            YjrItem::N(n) => {
+                stack.push_number( std::mem::replace(&mut self.number, n) );
+            },
+            YjrItem::V(v) => {
+                let size = v.vec().size();
+                let out = output(&mut self.ov, size);
+                // the input is fed back when it is our own output of last block
+                let same = std::ptr::eq(&*v.vec(), &*out.vec());
+                if self.held.len() != size {
+                    // silent after a change of length
+                    self.held.clear();
+                    self.held.resize(size, 0.0);
+                }
+                out.vec_mut().mut_data().swap_with_slice(&mut self.held);
+                if !same {
+                    // the input may be a buffer reused by its word, keep a copy
+                    self.held.copy_from_slice( v.vec().data() );
+                }
+                stack.push_vector(out);
+            },
+            _ => panic!("prev~ needs a number or a vector!"),
+        }
+    }
+}
+
+// v last -> n
+//
+// The final sample of the vector taken at the last run, zero at first.
+pub struct LastWord {
+    last: TNT,
+}
+impl LastWord {
+    pub fn new(_env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( LastWord {
+            last: 0.0,
+        })
+    }
+}
+impl NativeWord for LastWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let v = stack.pop_vector();
+        let prev = self.last;
+        if let Some(x) = v.vec().data().last() {
+            self.last = *x;
+        }
+        stack.push_number(prev);
+    }
+}
+
+// n accum -> n, v accum -> v
+//
+// Running sum across blocks, of numbers once per block or of every sample
+// of vectors.
+pub struct AccumWord {
+    sum: f64,
+    ov: Option<SharedVector>,
+}
+impl AccumWord {
+    pub fn new(_env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( AccumWord {
+            sum: 0.0,
+            ov: None,
+        })
+    }
+}
+impl NativeWord for AccumWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        match stack.pop() {
+            YjrItem::N(n) => {
+                self.sum += n as f64;
+                stack.push_number(self.sum as TNT);
+            },
+            YjrItem::V(v) => {
+                let x = v.vec();
+                let out = output(&mut self.ov, x.size());
+                {
+                    let mut y = out.vec_mut();
+                    for (i, v) in x.data().iter().enumerate() {
+                        self.sum += *v as f64;
+                        y[i] = self.sum as TNT;
+                    }
+                }
+                stack.push_vector(out);
+            },
+            _ => panic!("accum needs a number or a vector!"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TNT;
+    use crate::runtime::{YjrEnviroment, YjrRuntime};
+
+    fn block(rt: &mut YjrRuntime) -> Vec<TNT> {
+        rt.run();
+        rt.stack.pop().as_vector().vec().data().clone()
+    }
+
+    #[test]
+    fn prev() {
+        let mut env = YjrEnviroment::new(44100);
+        let mut rt = env.build("4 ones~ accum prev~");
+        assert_eq!(block(&mut rt), vec![0.0; 4]);
+        assert_eq!(block(&mut rt), vec![1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(block(&mut rt), vec![5.0, 6.0, 7.0, 8.0]);
+
+        // the current block is kept below
+        let mut rt = env.build("4 ones~ accum dup prev~");
+        block(&mut rt);
+        rt.stack.pop();
+        assert_eq!(block(&mut rt), vec![1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(rt.stack.pop().as_vector().vec().data(), &vec![5.0, 6.0, 7.0, 8.0]);
+
+        let mut rt = env.build("1 accum prev~");
+        let mut values = Vec::new();
+        for _ in 0..3 {
+            rt.run();
+            values.push(rt.stack.pop().as_number());
+        }
+        assert_eq!(values, vec![0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn last_and_accum() {
+        let mut env = YjrEnviroment::new(44100);
+        let mut rt = env.build("3 ones~ accum dup last swap drop 0.5 accum");
+        let mut values = Vec::new();
+        for _ in 0..3 {
+            rt.run();
+            let a = rt.stack.pop().as_number();
+            let l = rt.stack.pop().as_number();
+            values.push( (l, a) );
+        }
+        assert_eq!(values, vec![(0.0, 0.5), (3.0, 1.0), (6.0, 1.5)]);
+    }
+}
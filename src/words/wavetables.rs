@@ -0,0 +1,228 @@
+use crate::TNT;
+use crate::vector::fft::{FFT, Complex};
+use crate::runtime::{YjrEnviroment, NativeWord, YjrStack, YjrItem, SharedVector};
+use crate::words::{output, Control};
+
+/// Single cycle tables with octave mipmaps, level `j` keeps the harmonics
+/// up to `size / 2 >> j`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Wavetable {
+    size: usize,
+    tables: Vec<Vec<Vec<TNT>>>,     // table, level, sample
+}
+
+impl Wavetable {
+    pub fn new(tables: &[Vec<TNT>]) -> Self {
+        if tables.is_empty() || tables[0].len() < 2 {
+            panic!("Wavetable needs at least one table of two samples!");
+        }
+        let size = tables[0].len();
+        if tables.iter().any(|t| t.len() != size) {
+            panic!("Wavetables for morphing must have same length!");
+        }
+
+        let mut plan = FFT::<f64>::new(size);
+        let mut levels = 1;
+        while (size / 2) >> levels > 0 {
+            levels += 1;
+        }
+
+        let tables = tables.iter().map(|t| {
+            let mut spectrum: Vec<Complex<f64>> = t.iter().map(|v| Complex::new(*v as f64, 0.0)).collect();
+            plan.forward(&mut spectrum);
+            (0..levels).map(|j| {
+                let top = (size / 2) >> j;
+                let mut s = spectrum.clone();
+                for (k, c) in s.iter_mut().enumerate() {
+                    let h = k.min(size - k);
+                    if h > top {
+                        *c = Complex::new(0.0, 0.0);
+                    }
+                }
+                plan.inverse(&mut s);
+                s.iter().map(|c| c.re as TNT).collect()
+            }).collect()
+        }).collect();
+
+        Wavetable {
+            size,
+            tables,
+        }
+    }
+
+    /// Number of tables to morph.
+    pub fn count(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// The level without harmonics above Nyquist at `dt` cycles a sample.
+    pub fn level(&self, dt: f64) -> usize {
+        let allowed = if dt > 0.0 { (0.5 / dt).floor() as usize } else { usize::MAX };
+        let last = self.tables[0].len() - 1;
+        (0..=last).find(|j| (self.size / 2) >> j <= allowed).unwrap_or(last)
+    }
+
+    /// Linear interpolated read of a table level at `phase` in 0..1.
+    pub fn read(&self, table: usize, level: usize, phase: f64) -> TNT {
+        let t = &self.tables[table][level];
+        let x = phase * self.size as f64;
+        let i = x.floor() as usize % self.size;
+        let f = x.fract() as TNT;
+        let (a, b) = (t[i], t[(i + 1) % self.size]);
+        a + (b - a) * f
+    }
+
+    /// Read with morph position in 0..count()-1 between tables.
+    pub fn morph(&self, position: TNT, level: usize, phase: f64) -> TNT {
+        let position = position.clamp(0.0, (self.count() - 1) as TNT);
+        let i = (position.floor() as usize).min(self.count() - 1);
+        let f = position - i as TNT;
+        let a = self.read(i, level, phase);
+        if f == 0.0 {
+            return a;
+        }
+        a + (self.read(i + 1, level, phase) - a) * f
+    }
+}
+
+// n table freq morph wavetable~ -> v
+//
+// The table is a vector, or a multichannel item of tables to morph. Tables
+// are taken at the first run, freq and morph are numbers or vectors.
+pub struct WavetableWord {
+    rate: f64,
+    phase: f64,
+    table: Option<Wavetable>,
+    ov: Option<SharedVector>,
+}
+impl WavetableWord {
+    pub fn new(env: &YjrEnviroment) -> Box<dyn NativeWord> {
+        Box::new( WavetableWord {
+            rate: env.query("SampleRate").0 as f64,
+            phase: 0.0,
+            table: None,
+            ov: None,
+        })
+    }
+}
+impl NativeWord for WavetableWord {
+    fn run(&mut self, stack: &mut YjrStack) {
+        let n = stack.size();
+        if n < 4 {
+            panic!("wavetable~ needs a length, tables, frequency and morph position");
+        }
+        let count = match &stack.items()[n - 4] {
+            YjrItem::N(c) if c.fract() == 0.0 && *c >= 0.0 => *c as usize,
+            _ => panic!("Oscillator length must be a integer!"),
+        };
+
+        let morph = Control::pop(stack, count).values(count);
+        let freq = Control::pop(stack, count).values(count);
+        let tables = stack.pop();
+        stack.pop_number();
+
+        if self.table.is_none() {
+            let tables: Vec<Vec<TNT>> = match tables {
+                YjrItem::V(v) => vec![ v.vec().data().clone() ],
+                YjrItem::M(m) => m.iter().map(|v| v.vec().data().clone()).collect(),
+                _ => panic!("wavetable~ needs a table vector or multichannel tables!"),
+            };
+            self.table = Some( Wavetable::new(&tables) );
+        }
+
+        let table = self.table.as_ref().unwrap();
+        let out = output(&mut self.ov, count);
+        {
+            let mut y = out.vec_mut();
+            for i in 0..count {
+                let dt = freq[i] as f64 / self.rate;
+                y[i] = table.morph(morph[i], table.level(dt.abs()), self.phase);
+                self.phase = (self.phase + dt).rem_euclid(1.0);
+            }
+        }
+        stack.push_vector(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use crate::TNT;
+    use crate::vector::fft::{FFT, Complex};
+    use crate::runtime::SharedVector;
+    use crate::vector::Vector;
+    use crate::words::testing::drive;
+    use crate::words::wavetables::{Wavetable, WavetableWord};
+
+    fn saw(size: usize) -> Vec<TNT> {
+        (0..size).map(|i| 2.0 * i as TNT / size as TNT - 1.0).collect()
+    }
+
+    #[test]
+    fn mipmaps() {
+        let t = Wavetable::new(&[saw(2048)]);
+        assert_eq!(t.level(0.0), 0);
+        assert_eq!(t.level(1.0 / 4096.0), 0);
+        assert_eq!(t.level(1.0 / 1024.0), 1);
+        assert_eq!(t.level(1.0 / 1000.0), 2);
+        assert_eq!(t.level(0.4), 10);
+
+        // the last level is the fundamental only
+        for i in 0..2048 {
+            let p = i as f64 / 2048.0;
+            let expected = -2.0 / PI * (2.0 * PI * p).sin();
+            assert!((t.read(0, 10, p) as f64 - expected).abs() < 2.0e-3);
+        }
+    }
+
+    // runs the word over `n` samples in blocks of 512 at 44100 Hz
+    fn play(tables: Vec<Vec<TNT>>, freq: TNT, morph: &dyn Fn(usize) -> TNT, n: usize) -> Vec<TNT> {
+        drive(WavetableWord::new, 44100, 512, n, &|stack, start, block| {
+            stack.push_number(block as TNT);
+            if tables.len() == 1 {
+                stack.push_vector( SharedVector::new(Vector::new(tables[0].clone())) );
+            } else {
+                stack.push_multi( tables.iter().map(|t| SharedVector::new(Vector::new(t.clone()))).collect() );
+            }
+            stack.push_number(freq);
+            stack.push_vector( SharedVector::new(Vector::from_fn(block, |i| morph(start + i))) );
+        })
+    }
+
+    #[test]
+    fn band_limited_saw() {
+        // 1001 cycles in 8192 samples, aliases fall between harmonics
+        let n = 8192;
+        let freq = 1001.0 * 44100.0 / n as f64;
+        let y = play(vec![saw(2048)], freq as TNT, &|_| 0.0, n);
+
+        let mut s: Vec<Complex<f64>> = y.iter().map(|v| Complex::new(*v as f64, 0.0)).collect();
+        FFT::new(n).forward(&mut s);
+        let (mut total, mut alias) = (0.0, 0.0);
+        for (k, c) in s.iter().enumerate().take(n / 2).skip(1) {
+            total += c.norm_sqr();
+            if k % 1001 != 0 {
+                alias += c.norm_sqr();
+            }
+        }
+        assert!(10.0 * (alias / total).log10() < -40.0);
+    }
+
+    #[test]
+    fn morphing() {
+        let sine: Vec<TNT> = (0..256).map(|i| (2.0 * std::f32::consts::PI * i as TNT / 256.0).sin()).collect();
+        let zero = vec![0.0; 256];
+
+        // halfway between a sine and silence
+        let y = play(vec![sine.clone(), zero.clone()], 44100.0 / 256.0, &|_| 0.5, 1024);
+        for (i, v) in y.iter().enumerate() {
+            let expected = 0.5 * (2.0 * PI * i as f64 / 256.0).sin();
+            assert!((*v as f64 - expected).abs() < 1.0e-3);
+        }
+
+        // per-sample morph from sine to silence
+        let y = play(vec![sine, zero], 44100.0 / 256.0, &|i| i as TNT / 1023.0, 1024);
+        assert!(y[64].abs() > 0.9);
+        assert!(y[1024 - 256 + 64].abs() < 0.3);
+    }
+}